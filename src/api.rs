@@ -1,83 +1,202 @@
+#![allow(non_snake_case, dead_code)] // field names mirror the portal JSON, not everything is displayed (yet)
+
 // API access and data structures
 
 // Status
 
 use std::{
-    error::Error,
-    fs::{self, File},
-    io::Read,
+    fs, io,
     path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
 };
 
 use rand::Rng;
-use serde::Deserialize;
+use reqwest::blocking::Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
 
-#[derive(Default, Deserialize, Debug)]
+use crate::stats;
+
+#[derive(Error, Debug)]
+pub enum ApiError {
+    #[error("HTTP: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("unreadable response: {0}")]
+    Decode(#[from] serde_json::Error),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("no active trip")]
+    NotActive, // the portal answered, but we're not on a (known) train
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(default)]
 pub struct ApiEndpoints {
     pub status: String,
     pub trip: String,
 }
 
+impl Default for ApiEndpoints {
+    fn default() -> ApiEndpoints {
+        ApiEndpoints::at("https://iceportal.de")
+    }
+}
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+impl ApiEndpoints {
+    // the portal's paths below some host
+    pub fn at(base: &str) -> ApiEndpoints {
+        let base = base.trim_end_matches('/');
+        ApiEndpoints {
+            status: format!("{}/api1/rs/status", base),
+            trip: format!("{}/api1/rs/tripInfo/trip", base),
+        }
+    }
+
+    // not every train answers at the same host: the first one with a readable status, if any;
+    // on_probe sees every host before it is tried, each can take up to PROBE_TIMEOUT
+    pub fn detect(client: &Client, portals: &[String], mut on_probe: impl FnMut(&str)) -> Option<ApiEndpoints> {
+        portals.iter()
+            .find(|base| {
+                on_probe(base);
+                probe(client, &ApiEndpoints::at(base).status)
+            })
+            .map(|base| ApiEndpoints::at(base))
+    }
+
+    // when nobody answered, so the usual error shows up
+    pub fn first(portals: &[String]) -> ApiEndpoints {
+        portals.first().map(|base| ApiEndpoints::at(base)).unwrap_or_default()
+    }
+}
+
+// a readable status has the required fields, which a login page or another host's JSON doesn't
+fn probe(client: &Client, endpoint: &str) -> bool {
+    client.get(endpoint)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .is_ok_and(|text| serde_json::from_str::<StatusInfo>(&text).is_ok())
+}
+
 #[derive(Default, Deserialize, Debug)]
 pub struct ApiPaths {
     pub status: PathBuf,
     pub trip: PathBuf,
 }
 
-#[derive(Default, Deserialize, Debug)]
+// one client for all queries so connections to the portal are kept alive
+pub fn client() -> Result<Client, ApiError> {
+    let client = Client::builder()
+        .user_agent("Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0")
+        .build()?;
+    Ok(client)
+}
+
+// the body of a successful response
+pub fn fetch_text(client: &Client, endpoint: &str) -> Result<String, ApiError> {
+    let response = client.get(endpoint).send()?.error_for_status()?;
+    Ok(response.text()?)
+}
+
+// read first so broken JSON is told apart from network trouble
+fn fetch<T: DeserializeOwned>(client: &Client, endpoint: &str) -> Result<T, ApiError> {
+    Ok(serde_json::from_str(&fetch_text(client, endpoint)?)?)
+}
+
+// where the frontend gets its data from
+#[derive(Debug)]
+pub enum DataSource {
+    Api(ApiEndpoints),
+    Files(ApiPaths), // recorded samples for testing without a train
+}
+
+// fields a panel can do without fall back to defaults when the portal leaves them out, so a
+// changed API degrades that panel; the times, positions and stations everything else is
+// computed from are required, an error object or a half answer fails the update instead
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct Connectivity {
-    currentState: String,
-    nextState: String,
-    remainingTimeSeconds: u64,
+    #[serde(default)]
+    pub currentState: String,
+    pub nextState: Option<String>,
+    pub remainingTimeSeconds: Option<u64>, // until nextState is reached
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct StatusInfo {
+    #[serde(default)]
     pub connection: bool, // no idea what is is
+    #[serde(default)]
     pub serviceLevel: String,
+    #[serde(default)]
     pub gpsStatus: String,
+    #[serde(default)]
     pub internet: String,
+    #[serde(default)]
     pub latitude: f64,
+    #[serde(default)]
     pub longitude: f64,
+    #[serde(default)]
     pub tileY: i64,
+    #[serde(default)]
     pub tileX: i64,
-    pub series: String, // TODO parse in a better way (I'm not a train nerd so w/e)
+    #[serde(default)]
+    pub series: String, // Baureihe, see series_name
     pub serverTime: u64,
+    #[serde(default)]
     pub speed: f64,
+    #[serde(default)]
     pub trainType: String,
+    #[serde(default)]
     pub tzn: String, // train number
+    #[serde(default)]
     pub wagonClass: String,
-    connectivity: Connectivity,
+    #[serde(default)]
+    pub connectivity: Connectivity,
+    #[serde(default)]
     pub bapInstalled: bool, // bap = bahn-api ?
 }
 
 // Trip
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct TripStopInfo {
+    #[serde(default)]
     pub scheduledNext: String,
+    #[serde(default)]
     pub actualNext: String,
+    #[serde(default)]
     pub actualLast: String,
+    #[serde(default)]
     pub actualLastStarted: String,
+    #[serde(default)]
     pub finalStationName: String,
+    #[serde(default)]
     pub finalStationEvaNr: String,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct GeoCoordinates {
+    #[serde(default)]
     pub latitude: f64,
+    #[serde(default)]
     pub longitude: f64,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct Station {
     pub evaNr: String,
+    #[serde(default)]
     pub name: String,
     pub code: Option<String>,
+    #[serde(default)]
     pub geocoordinates: GeoCoordinates,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct Timetable {
     pub scheduledArrivalTime: Option<u64>, // option since no arrival at first station
     pub actualArrivalTime: Option<u64>,
@@ -89,36 +208,49 @@ pub struct Timetable {
     pub departureDelay: Option<String>,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct Track {
+    #[serde(default)]
     pub scheduled: String,
+    #[serde(default)]
     pub actual: String,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct StopInfo {
+    #[serde(default)]
     pub status: u64,
+    #[serde(default)]
     pub passed: bool,
+    #[serde(default)]
     pub positionStatus: String,
+    #[serde(default)]
     pub distance: u64,
+    #[serde(default)]
     pub distanceFromStart: u64,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct DelayReason {
-    // TODO can't fill this out yet (the train is actually on time)
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub text: String,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct Stop {
     pub station: Station,
+    #[serde(default)]
     pub timetable: Timetable,
+    #[serde(default)]
     pub track: Track,
+    #[serde(default)]
     pub info: StopInfo,
-    pub delay_reasons: Option<Vec<DelayReason>>,
+    pub delayReasons: Option<Vec<DelayReason>>,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct Connection {
     pub trainType: Option<String>,
     pub vzn: Option<String>,
@@ -128,73 +260,192 @@ pub struct Connection {
     pub track: Option<Track>,
     pub info: Option<TripStopInfo>,
     pub stops: Option<Vec<Stop>>,
+    #[serde(default)]
     pub conflict: String,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct Trip {
+    #[serde(default)]
     pub tripDate: String,
+    #[serde(default)]
     pub trainType: String,
+    #[serde(default)]
     pub vzn: String, // train identifier
     pub actualPosition: u64,
+    #[serde(default)]
     pub distanceFromLastStop: u64,
     pub totalDistance: u64,
+    #[serde(default)]
     pub stopInfo: TripStopInfo,
+    #[serde(default)]
     pub stops: Vec<Stop>,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct TripInfo {
     pub trip: Trip,
+    #[serde(default)]
     pub connection: Connection,
     pub active: Option<bool>,
 }
 
-#[derive(Default, Deserialize, Debug)]
+#[derive(Default, Clone, Deserialize, Serialize, Debug)]
 pub struct Info {
     pub status: StatusInfo,
     pub trip: TripInfo,
+    #[serde(default)]
+    pub speed_estimated: bool, // ours, not the portal's: status.speed was taken from the GPS positions, see gps_speed
+}
+
+// connection quality as the portal reports it in internet and connectivity, worst first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Signal {
+    Unknown, // NO_INFO or anything new
+    Weak,
+    Unstable,
+    Middle,
+    High,
+}
+
+impl Signal {
+    pub fn parse(state: &str) -> Signal {
+        match state {
+            "WEAK" | "LOW" => Signal::Weak,
+            "UNSTABLE" => Signal::Unstable,
+            "MIDDLE" => Signal::Middle,
+            "HIGH" => Signal::High,
+            _ => Signal::Unknown,
+        }
+    }
+
+    // 0 for unknown up to 4 for high
+    pub fn level(self) -> u8 {
+        self as u8
+    }
+}
+
+// where the train is relative to a stop, finer than passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopPosition {
+    Passed,
+    Departed, // the stop it left last
+    Arrived, // standing there
+    Future,
 }
 
+impl StopInfo {
+    // positionStatus, going by passed for anything new
+    pub fn position(&self) -> StopPosition {
+        match self.positionStatus.as_str() {
+            "passed" => StopPosition::Passed,
+            "departed" => StopPosition::Departed,
+            "arrived" => StopPosition::Arrived,
+            "future" => StopPosition::Future,
+            _ if self.passed => StopPosition::Passed,
+            _ => StopPosition::Future,
+        }
+    }
+}
+
+// Baureihen of the ICE fleet and what everyone calls them
+const SERIES: [(&str, &str); 10] = [
+    ("401", "ICE 1"),
+    ("402", "ICE 2"),
+    ("403", "ICE 3"),
+    ("406", "ICE 3M"),
+    ("407", "ICE 3 Velaro D"),
+    ("408", "ICE 3neo"),
+    ("411", "ICE T"),
+    ("412", "ICE 4"),
+    ("415", "ICE T"),
+    ("605", "ICE TD"),
+];
+
 impl StatusInfo {
-    pub fn query(endpoint: &str) -> Result<StatusInfo, reqwest::Error> {
-        let client = reqwest::blocking::Client::new();
+    // None for codes we don't know (yet)
+    pub fn series_name(&self) -> Option<&'static str> {
+        let code = self.series.trim_start_matches(|c: char| !c.is_ascii_digit());
+        SERIES.iter().find(|(series, _)| *series == code).map(|(_, name)| *name)
+    }
+
+    // km/h from the distance to an earlier position, for when the portal's speed is stuck at 0;
+    // None if the train didn't (noticeably) move or the jump is too large to be real
+    pub fn gps_speed(&self, previous: &StatusInfo) -> Option<f64> {
+        let seconds = self.serverTime.checked_sub(previous.serverTime).filter(|&ms| ms > 0)? as f64 / 1000.0;
+        let meters = haversine((previous.latitude, previous.longitude), (self.latitude, self.longitude));
+        let kmh = meters / seconds * 3.6;
+        (GPS_MIN_SPEED..=GPS_MAX_SPEED).contains(&kmh).then_some(kmh)
+    }
+}
+
+// anything slower is GPS noise while standing, anything faster a bad fix
+const GPS_MIN_SPEED: f64 = 5.0;
+const GPS_MAX_SPEED: f64 = 400.0;
+
+// great circle distance in meters, from and to are (latitude, longitude)
+fn haversine(from: (f64, f64), to: (f64, f64)) -> f64 {
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let (dlat, dlon) = ((to.0 - from.0).to_radians(), (to.1 - from.1).to_radians());
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+impl Connection {
+    // the portal only fills this in if it knows about an onward train
+    pub fn is_known(&self) -> bool {
+        self.trainNumber.is_some() || self.vzn.is_some() || self.station.is_some()
+    }
+
+    pub fn is_conflict(&self) -> bool {
+        !self.conflict.is_empty() && self.conflict != "NO_CONFLICT"
+    }
+}
 
-        let response = client
-            .get(endpoint)
-            .header(
-                "User-Agent",
-                "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
-            )
-            .send()?;
-        let deserialized = response.json()?;
-        Ok(deserialized)
+impl Trip {
+    // the stop the train is heading to, the portal's own guess if it differs from the plan
+    pub fn next_stop(&self) -> Option<&Stop> {
+        let find = |eva: &str| self.stops.iter().find(|stop| stop.station.evaNr == eva);
+        find(&self.stopInfo.actualNext).or_else(|| find(&self.stopInfo.scheduledNext))
     }
 
-    pub fn from_file(path: &Path) -> Result<StatusInfo, Box<dyn Error>> {
+    // the final stop's delay in minutes, None before the portal knows it
+    pub fn final_delay(&self) -> Option<i64> {
+        let last = self.stops.iter().find(|stop| stop.station.evaNr == self.stopInfo.finalStationEvaNr)
+            .or(self.stops.last())?;
+        let timetable = &last.timetable;
+        Some(stats::delay_minutes(timetable.scheduledArrivalTime?, timetable.actualArrivalTime?))
+    }
+}
+
+impl Stop {
+    // meters left from a position (meters from the start) to this stop
+    pub fn distance_from(&self, position: u64) -> u64 {
+        self.info.distanceFromStart.saturating_sub(position)
+    }
+}
+
+impl StatusInfo {
+    pub fn query(client: &Client, endpoint: &str) -> Result<StatusInfo, ApiError> {
+        fetch(client, endpoint)
+    }
+
+    pub fn from_file(path: &Path) -> Result<StatusInfo, ApiError> {
         let content = fs::read_to_string(path)?;
         let mut status: StatusInfo = serde_json::from_str(&content)?;
         status.speed = rand::thread_rng().gen_range(0.0..300.0);
+        status.serverTime = chrono::Utc::now().timestamp_millis() as u64; // replayed, so it is fresh
         Ok(status)
     }
 }
 
 impl TripInfo {
-    pub fn query(endpoint: &str) -> Result<TripInfo, reqwest::Error> {
-        let client = reqwest::blocking::Client::new();
-
-        let response = client
-            .get(endpoint)
-            .header(
-                "User-Agent",
-                "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
-            )
-            .send()?;
-        let deserialized = response.json()?;
-        Ok(deserialized)
+    pub fn query(client: &Client, endpoint: &str) -> Result<TripInfo, ApiError> {
+        fetch(client, endpoint)
     }
 
-    pub fn from_file(path: &Path) -> Result<TripInfo, Box<dyn Error>> {
+    pub fn from_file(path: &Path) -> Result<TripInfo, ApiError> {
         let content = fs::read_to_string(path)?;
         let trip: TripInfo = serde_json::from_str(&content)?;
         Ok(trip)
@@ -202,17 +453,80 @@ impl TripInfo {
 }
 
 impl Info {
-    pub fn query(endpoints: &ApiEndpoints) -> Result<Info, reqwest::Error> {
-        let status = StatusInfo::query(&endpoints.status)?;
-        let trip = TripInfo::query(&endpoints.trip)?;
+    pub fn query(client: &Client, endpoints: &ApiEndpoints) -> Result<Info, ApiError> {
+        Info::query_cached(client, endpoints, None)
+    }
 
-        Ok(Info { status, trip })
+    // only the status if the trip is known already, it hardly changes from one update to the next
+    pub fn query_cached(client: &Client, endpoints: &ApiEndpoints, trip: Option<&TripInfo>) -> Result<Info, ApiError> {
+        let status = StatusInfo::query(client, &endpoints.status)?;
+        let trip = match trip {
+            Some(trip) => trip.clone(),
+            None => TripInfo::query(client, &endpoints.trip)?,
+        };
+
+        Info { status, trip, speed_estimated: false }.check_active()
+    }
+
+    // everything that draws a trip relies on there being stops
+    fn check_active(self) -> Result<Info, ApiError> {
+        if self.trip.active == Some(false) || self.trip.trip.stops.is_empty() {
+            Err(ApiError::NotActive)
+        } else {
+            Ok(self)
+        }
+    }
+
+    // retry transient failures (tunnels...) with doubling delays, sleeping at most `budget` in total;
+    // on_retry sees every failed attempt that is retried
+    pub fn query_with_retry(
+        client: &Client,
+        endpoints: &ApiEndpoints,
+        trip: Option<&TripInfo>,
+        max_attempts: u32,
+        base_delay: Duration,
+        budget: Duration,
+        mut on_retry: impl FnMut(u32, &ApiError),
+    ) -> Result<Info, ApiError> {
+        let start = Instant::now();
+        let mut delay = base_delay;
+        let mut attempt = 1;
+
+        loop {
+            match Info::query_cached(client, endpoints, trip) {
+                Ok(info) => return Ok(info),
+                Err(e) => {
+                    let remaining = budget.saturating_sub(start.elapsed());
+                    if attempt >= max_attempts || !is_transient(&e) || remaining < delay {
+                        return Err(e);
+                    }
+                    on_retry(attempt, &e);
+                }
+            }
+
+            thread::sleep(delay);
+            delay *= 2;
+            attempt += 1;
+        }
     }
 
-    pub fn from_file(paths: &ApiPaths) -> Result<Info, Box<dyn Error>> {
+    pub fn from_file(paths: &ApiPaths) -> Result<Info, ApiError> {
         let status = StatusInfo::from_file(&paths.status)?;
         let trip = TripInfo::from_file(&paths.trip)?;
 
-        Ok(Info { status, trip })
+        Info { status, trip, speed_estimated: false }.check_active()
+    }
+}
+
+// network hiccups are worth retrying, garbage responses are not
+fn is_transient(e: &ApiError) -> bool {
+    match e {
+        ApiError::Http(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.is_request()
+                || e.status().is_some_and(|status| status.is_server_error())
+        }
+        _ => false,
     }
 }
@@ -0,0 +1,90 @@
+// --check: one query and a report of what came back, for when the UI shows nothing
+
+use std::{fs, path::Path};
+
+use reqwest::blocking::Client;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::api::{self, ApiError, DataSource, Info, StatusInfo, TripInfo};
+
+// fields we read that the response left out or sent as null, nested ones as a.b
+fn missing(raw: &Value, expected: &Value, prefix: &str, found: &mut Vec<String>) {
+    let Value::Object(fields) = expected else { return };
+    for (name, template) in fields {
+        let path = format!("{}{}", prefix, name);
+        match raw.get(name) {
+            None | Some(Value::Null) => found.push(path),
+            Some(value) => missing(value, template, &format!("{}.", path), found),
+        }
+    }
+}
+
+// raw and parsed, printing what went wrong
+fn load<T: DeserializeOwned + Serialize + Default>(name: &str, read: Result<String, ApiError>) -> Option<T> {
+    let text = match read {
+        Ok(text) => text,
+        Err(e) => {
+            println!("  failed: {}", e);
+            return None;
+        }
+    };
+    let parsed = match serde_json::from_str::<T>(&text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("  unreadable {}: {}", name, e);
+            return None;
+        }
+    };
+
+    // parsing went through, so this is JSON
+    let raw: Value = serde_json::from_str(&text).unwrap_or_default();
+    let expected = serde_json::to_value(T::default()).unwrap_or_default();
+    let mut found = Vec::new();
+    missing(&raw, &expected, "", &mut found);
+    if found.is_empty() {
+        println!("  complete");
+    } else {
+        println!("  missing: {}", found.join(", "));
+    }
+    Some(parsed)
+}
+
+// true if the UI would have something to show
+pub fn run(client: &Client, source: &DataSource, config: &str, endpoints: &str) -> bool {
+    println!("config:    {}", config);
+    println!("endpoints: {}", endpoints);
+
+    let (status, trip) = match source {
+        DataSource::Api(endpoints) => {
+            println!("status:    {}", endpoints.status);
+            let status = load::<StatusInfo>("status", api::fetch_text(client, &endpoints.status));
+            println!("trip:      {}", endpoints.trip);
+            (status, load::<TripInfo>("trip", api::fetch_text(client, &endpoints.trip)))
+        }
+        DataSource::Files(paths) => {
+            let read = |path: &Path| fs::read_to_string(path).map_err(ApiError::from);
+            println!("status:    {}", paths.status.display());
+            let status = load::<StatusInfo>("status", read(&paths.status));
+            println!("trip:      {}", paths.trip.display());
+            (status, load::<TripInfo>("trip", read(&paths.trip)))
+        }
+    };
+
+    let (Some(status), Some(trip)) = (status, trip) else {
+        println!("result:    failed");
+        return false;
+    };
+
+    let active = trip.active != Some(false) && !trip.trip.stops.is_empty();
+    println!("train:     {} {}, series {}", status.trainType, trip.trip.vzn,
+        status.series_name().unwrap_or(&status.series));
+    println!("trip:      {} stops, {}", trip.trip.stops.len(), if active { "active" } else { "not active" });
+    let info = Info { status, trip, speed_estimated: false };
+    if let Some(next) = info.trip.trip.next_stop() {
+        println!("next stop: {}", next.station.name);
+    }
+
+    println!("result:    {}", if active { "ok" } else { "no active trip" });
+    active
+}
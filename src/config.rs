@@ -0,0 +1,177 @@
+// Settings from ~/.config/bahn-status/config.toml
+
+use std::{
+    env,
+    error::Error,
+    fs, io,
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::{api::ApiEndpoints, keymap::Keymap, labels::Language, theme::Theme, units::{Clock, Units}};
+
+// faster than this would only keep the portal (and the CPU) busy
+const MIN_TICK_RATE_MS: u64 = 100;
+const MIN_REDRAW_MS: u64 = 20;
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub endpoints: Option<ApiEndpoints>, // fixed endpoints, skips trying the portals
+    pub portals: Vec<String>, // hosts tried in order on startup
+    #[serde(alias = "poll_ms")]
+    pub tick_rate_ms: u64, // milliseconds between queries
+    pub redraw_ms: u64, // milliseconds between redraws, independent of the queries
+    pub trip_interval_s: u64, // seconds between fetching the trip, the status comes every tick
+    pub history: usize, // number of buffered updates
+    pub keep_history: bool, // save them, and continue with them after a restart on the same train
+    pub average_window: usize, // number of updates in the moving average speed
+    pub language: Language,
+    pub log_csv: Option<PathBuf>,
+    pub log: Option<PathBuf>, // outcome of every update, errors and retries
+    pub serve: Option<u16>, // port for the local /status endpoint
+    pub metrics: Option<u16>, // port for the local /metrics endpoint
+    pub keys: Keymap,
+    pub confirm_quit: bool, // ask before quitting so the history isn't lost by accident
+    pub exit_summary: bool, // print what the session added up to after quitting
+    pub on_arrival: OnArrival,
+    pub approach_alert_km: Option<f64>, // banner once the train gets this close to the selected station
+    pub delay_alert_min: Option<i64>, // banner once the delay at the destination grew by this many minutes
+    pub alert_bell: bool, // ring the terminal bell along with the alerts
+    pub wrap_stations: bool, // station selection goes round at the ends of the list
+    pub hide_passed: bool, // leave passed stops out of the trip panel
+    pub sparkline: bool, // compact speed history instead of the graph
+    pub graph_max_speed: Option<f64>, // fixed top of the speed graph in km/h, scaled to the data if not set
+    pub speed_colors: SpeedColors,
+    pub units: Units,
+    pub clock: Clock,
+    pub timezone: Option<Tz>, // e.g. "Europe/Berlin", defaults to the system's
+    pub delay_colors: DelayColors,
+    pub theme: Theme,
+}
+
+// how the speed graph is colored
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeedColors {
+    #[default]
+    Speed, // blue when slow, through green to red when fast
+    Direction, // red when slowing down, green when speeding up
+}
+
+// what happens once the train reaches the final stop
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OnArrival {
+    #[default]
+    Nothing,
+    Export, // GPX like the export key
+    Summary, // the exit summary in a popup
+    Both,
+}
+
+impl OnArrival {
+    pub fn export(self) -> bool {
+        matches!(self, OnArrival::Export | OnArrival::Both)
+    }
+
+    pub fn summary(self) -> bool {
+        matches!(self, OnArrival::Summary | OnArrival::Both)
+    }
+}
+
+// delays from yellow minutes on are yellow, from red minutes on red, anything below green
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct DelayColors {
+    pub yellow: i64,
+    pub red: i64,
+}
+
+impl Default for DelayColors {
+    fn default() -> DelayColors {
+        DelayColors {
+            yellow: 1,
+            red: 6,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            endpoints: None,
+            portals: vec![String::from("https://iceportal.de"), String::from("https://portal.imice.de")],
+            tick_rate_ms: 1000, // update every second
+            redraw_ms: 200,
+            trip_interval_s: 15,
+            history: 50,
+            keep_history: false,
+            average_window: 10,
+            language: Language::default(),
+            log_csv: None,
+            log: None,
+            serve: None,
+            metrics: None,
+            keys: Keymap::default(),
+            confirm_quit: true,
+            exit_summary: true,
+            on_arrival: OnArrival::default(),
+            approach_alert_km: None,
+            delay_alert_min: None,
+            alert_bell: false,
+            wrap_stations: false,
+            hide_passed: false,
+            sparkline: false,
+            graph_max_speed: None,
+            speed_colors: SpeedColors::default(),
+            units: Units::default(),
+            clock: Clock::default(),
+            timezone: None,
+            delay_colors: DelayColors::default(),
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Config {
+    // $XDG_CONFIG_HOME/bahn-status/config.toml, falling back to ~/.config
+    pub fn path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        Some(base.join("bahn-status").join("config.toml"))
+    }
+
+    // missing file means defaults (unless it was asked for explicitly), a broken one is an error
+    pub fn load(explicit: Option<PathBuf>) -> Result<Config, Box<dyn Error>> {
+        let required = explicit.is_some();
+        let Some(path) = explicit.or_else(Config::path) else {
+            return Ok(Config::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e).into()),
+        }
+    }
+
+    pub fn tick_rate(&self) -> Duration {
+        Duration::from_millis(self.tick_rate_ms.max(MIN_TICK_RATE_MS))
+    }
+
+    // at least once per query
+    pub fn redraw_interval(&self) -> Duration {
+        Duration::from_millis(self.redraw_ms.max(MIN_REDRAW_MS)).min(self.tick_rate())
+    }
+
+    pub fn trip_interval(&self) -> Duration {
+        Duration::from_secs(self.trip_interval_s)
+    }
+}
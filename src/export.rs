@@ -0,0 +1,87 @@
+// Export of the collected data to other formats
+
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::{self, Write as _},
+    path::Path,
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+use crate::api::Info;
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// GPX 1.1 with the planned route (stations of the newest trip) and the
+// track actually driven (buffered live positions)
+pub fn to_gpx(data: &VecDeque<Info>) -> String {
+    let mut gpx = String::new();
+
+    gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str("<gpx version=\"1.1\" creator=\"bahn-status\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+
+    if let Some(info) = data.back() {
+        let name = escape_xml(&format!("{} {}", info.trip.trip.trainType, info.trip.trip.vzn));
+
+        // writing into a String can't fail
+        writeln!(gpx, "  <rte>\n    <name>{}</name>", name).unwrap();
+        for stop in &info.trip.trip.stops {
+            let coordinates = &stop.station.geocoordinates;
+            writeln!(
+                gpx,
+                "    <rtept lat=\"{}\" lon=\"{}\"><name>{}</name></rtept>",
+                coordinates.latitude,
+                coordinates.longitude,
+                escape_xml(&stop.station.name)
+            )
+            .unwrap();
+        }
+        gpx.push_str("  </rte>\n");
+
+        writeln!(gpx, "  <trk>\n    <name>{}</name>\n    <trkseg>", name).unwrap();
+        for info in data {
+            write!(gpx, "      <trkpt lat=\"{}\" lon=\"{}\">", info.status.latitude, info.status.longitude).unwrap();
+            if let Some(time) = DateTime::from_timestamp_millis(info.status.serverTime as i64) {
+                write!(gpx, "<time>{}</time>", time.to_rfc3339_opts(SecondsFormat::Secs, true)).unwrap();
+            }
+            gpx.push_str("</trkpt>\n");
+        }
+        gpx.push_str("    </trkseg>\n  </trk>\n");
+    }
+
+    gpx.push_str("</gpx>\n");
+    gpx
+}
+
+// timestamped line in a plain text log
+pub fn append_log(path: &Path, message: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{} {}", Local::now().format("%Y-%m-%d %H:%M:%S"), message)
+}
+
+// one row per update, the header is only written into new (empty) files
+pub fn append_csv(path: &Path, info: &Info) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "serverTime,speed,actualPosition,latitude,longitude,vzn")?;
+    }
+
+    writeln!(
+        file,
+        "{},{},{},{},{},{}",
+        info.status.serverTime,
+        info.status.speed,
+        info.trip.trip.actualPosition,
+        info.status.latitude,
+        info.status.longitude,
+        info.trip.trip.vzn
+    )
+}
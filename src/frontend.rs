@@ -1,11 +1,13 @@
-use std::{collections::VecDeque, error::Error, io::{self, stdout, Stdout}, path::PathBuf, time::{Duration, Instant}};
+use std::{collections::VecDeque, error::Error, fs, io::{self, stdout, Write}, iter, mem, path::PathBuf, time::{Duration, Instant}};
 
-use chrono::{DateTime, Local, NaiveDateTime};
+use arboard::Clipboard;
+use chrono::{TimeDelta, Utc};
 use ratatui::{
-    backend::CrosstermBackend, crossterm::{event::{self, Event, KeyCode}, terminal::{enable_raw_mode, EnterAlternateScreen}, ExecutableCommand}, layout::{Constraint, Direction, Layout, Rect}, style::Color, text::{Line, Span, Text}, widgets::{self, canvas::{Canvas, Circle, Map, MapResolution, Shape}, Block, Paragraph}, Frame, Terminal
+    backend::CrosstermBackend, crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseEventKind}, layout::{Constraint, Direction, Layout, Rect}, style::{Color, Style, Stylize}, text::{Line, Span}, widgets::{self, canvas::{Canvas, Circle, Map, MapResolution, Points}, Block, Clear, LineGauge, Paragraph, Sparkline}, Frame, Terminal
 };
+use reqwest::blocking::Client;
 
-use crate::api::{ApiPaths, Info, Station, StatusInfo};
+use crate::{api::{ApiError, Connection, DataSource, Info, Signal, Stop, StopPosition}, config::{Config, DelayColors, OnArrival, SpeedColors}, export, history, keymap::{self, Action, Keymap}, labels::Labels, records::Records, query::{Response, Worker}, serve::Latest, state, stats, text::{self, aligned, ellipsize}, theme::{SpeedScale, Theme}, units::{self, TimeFormat, Units}};
 
 // +- Status information --------------------------
 // | Current Speed:      113
@@ -14,16 +16,18 @@ use crate::api::{ApiPaths, Info, Station, StatusInfo};
 // | Traveled so far:    73km
 // | Remaining:          339km
 // | Distance to next:   21km (Friedberg (Hess))
-// | Latitude/longitude: (50.57N, 8.66W)
+// | Latitude/longitude: (50.57N, 8.66E)
 // +-----------------------------------------------
 
 
-#[derive(Debug, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum PanelSelection {
     BasicInformation,
     StatusInformation,
     SpeedInformation,
     TripInformation,
+    MapInformation,
 }
 
 impl PanelSelection {
@@ -32,104 +36,619 @@ impl PanelSelection {
             PanelSelection::BasicInformation => PanelSelection::StatusInformation,
             PanelSelection::StatusInformation => PanelSelection::SpeedInformation,
             PanelSelection::SpeedInformation => PanelSelection::TripInformation,
-            PanelSelection::TripInformation => PanelSelection::BasicInformation,
+            PanelSelection::TripInformation => PanelSelection::MapInformation,
+            PanelSelection::MapInformation => PanelSelection::BasicInformation,
         }
     }
 
     pub fn prev(&mut self) {
         *self = match *self {
-            PanelSelection::BasicInformation => PanelSelection::TripInformation,
+            PanelSelection::BasicInformation => PanelSelection::MapInformation,
             PanelSelection::StatusInformation => PanelSelection::BasicInformation,
             PanelSelection::SpeedInformation => PanelSelection::StatusInformation,
             PanelSelection::TripInformation => PanelSelection::SpeedInformation,
+            PanelSelection::MapInformation => PanelSelection::TripInformation,
         }
     }
 }
 
+// rect of the given size centered in area (clamped to area)
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+// blue at 0, green at 0.5, red at 1 (of the graph's height), or straight from blue to orange
+fn speed_color(fraction: f64, scale: SpeedScale) -> Color {
+    let fraction = fraction.clamp(0.0, 1.0);
+    let blend = |amount: f64| (amount * 255.0).round() as u8;
+    match scale {
+        SpeedScale::Rainbow if fraction < 0.5 => Color::Rgb(0, blend(fraction * 2.0), blend(1.0 - fraction * 2.0)),
+        SpeedScale::Rainbow => Color::Rgb(blend(fraction * 2.0 - 1.0), blend(2.0 - fraction * 2.0), 0),
+        SpeedScale::BlueOrange => {
+            let mix = |from: f64, to: f64| blend((from + (to - from) * fraction) / 255.0);
+            Color::Rgb(mix(0.0, 230.0), mix(114.0, 159.0), mix(178.0, 0.0))
+        }
+    }
+}
+
+// initial great circle bearing in degrees from north, from and to are (latitude, longitude)
+fn bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let dlon = (to.1 - from.1).to_radians();
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+// indices of the stops the trip panel lists, the last one stays even if everything is passed
+fn listed(stops: &[Stop], hide_passed: bool) -> Vec<usize> {
+    let listed: Vec<usize> = (0..stops.len()).filter(|&i| !(hide_passed && stops[i].info.passed)).collect();
+    if listed.is_empty() {
+        stops.len().checked_sub(1).into_iter().collect()
+    } else {
+        listed
+    }
+}
+
+// bars like a phone's, filled up to the level
+fn signal_meter(signal: Signal) -> Vec<Span<'static>> {
+    let color = match signal {
+        Signal::High | Signal::Middle => Color::Green,
+        Signal::Unstable => Color::Yellow,
+        Signal::Weak => Color::Red,
+        Signal::Unknown => Color::DarkGray,
+    };
+
+    ["▁", "▃", "▅", "▇"].iter().enumerate()
+        .map(|(i, bar)| if (i as u8) < signal.level() { Span::raw(*bar).fg(color) } else { Span::raw(*bar).dark_gray() })
+        .collect()
+}
+
+// too small for all panels side by side, only the focused one is shown and Tab goes on to the next
+const COMPACT_WIDTH: u16 = 100;
+const COMPACT_HEIGHT: u16 = 31;
+
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(30); // records and history, and once more on exit
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_IDLE: &str = "·";
+const DELAY_ALERT_DURATION: Duration = Duration::from_secs(10);
+const DELAY_ALERT_INTERVAL: Duration = Duration::from_secs(5 * 60); // at most one alert this often
+const STALE_AFTER: TimeDelta = TimeDelta::seconds(30);
+const MAX_STATION_GAP: usize = 3; // rows between two stops when the list is short
+
+// the entry the panels show: the newest one, or the one scrubbed to
+fn shown(data: &VecDeque<Info>, scrub: Option<usize>) -> Option<&Info> {
+    match scrub {
+        Some(index) => data.get(index),
+        None => data.back(),
+    }
+}
+
 // variables preserved across draw calls
-#[derive(Debug)]
 pub struct Frontend {
     selection: PanelSelection,
+    labels: &'static Labels,
+    keymap: Keymap,
+    selected_station: usize, // index into the trip's stops
+    station_offset: usize, // first station shown in the (scrolled) trip panel
+    selected_station_detailed: bool,
+    wrap_stations: bool, // moving past the last station goes to the first and back
+    hide_passed: bool, // the trip panel only lists what's ahead
+    connection_stops: bool, // the onward train's route next to the trip, if the portal knows it
+    scrub: Option<usize>, // index into data shown instead of the newest entry
+    search: Option<(String, usize)>, // typed query and the selection to go back to on Esc
+    show_help: bool,
+    on_arrival: OnArrival,
+    arrival_summary: Option<String>, // popup once we got there, see on_arrival
+    debug: Option<u16>, // scroll offset of the raw data view, if shown
+    paused: bool, // no updates are fetched while set
+    confirm_quit: bool,
+    confirming_quit: bool, // quit was pressed and waits for y/n
+    sparkline: bool,
+    distance_graph: bool, // distance instead of speed over time
+    graph_max_speed: Option<f64>, // km/h
+    speed_colors: SpeedColors,
+    units: Units,
+    time_format: TimeFormat,
+    delay_colors: DelayColors,
+    theme: Theme,
+    queries: Worker, // owns the source and the client
     data: VecDeque<Info>, // server timestamp contained in status
+    history: usize, // how many entries data keeps
+    average_window: usize, // samples in the moving average
+    last_update: Instant, // when the newest entry of data arrived
+    trip_update: Option<Instant>, // when its trip was fetched, see trip_interval
+    trip_interval: Duration,
+    redraw_interval: Duration,
+    error: Option<String>, // last failed update, shown until the next successful one
+    not_active: bool, // the portal says we're not on a train
+    notice: Option<(String, Instant)>, // short-lived feedback for the user
+    fetching: bool, // a query is outstanding, no second one is sent meanwhile
+    refresh_pending: bool, // refresh was asked for while fetching, sent once that query is in
+    refresh_inflight: bool, // the outstanding query is a refresh, applied even while paused
+    approach_alert: Option<u64>, // meters before the selected station to alert at
+    approach_alerted: Option<String>, // evaNr of the stop it went off for, once per stop
+    delay_alert: Option<i64>, // minutes the final delay may grow before alerting
+    delay_baseline: Option<i64>, // lowest final delay since the last alert
+    delay_alerted: Option<(i64, i64, Instant)>, // from and to minutes, when
+    alert_bell: bool,
+    csv_log: Option<PathBuf>, // every update is appended here
+    log: Option<PathBuf>, // how every update went
+    latest: Option<Latest>, // handed out by the --serve thread
+    records: Option<(Records, PathBuf)>, // all-time records and where they are kept, not for replayed data
+    records_changed: bool, // not saved yet
+    record_to_beat: Option<f64>, // km/h the stored record had at startup, None once beaten or if there was none
+    history_file: Option<PathBuf>, // where data is saved with keep_history
+    restore: Option<VecDeque<Info>>, // saved by the previous run, used if the first update continues it
+    state_saved: Instant,
+    panel_areas: Vec<(PanelSelection, Rect)>, // where the last draw put each panel, for mouse clicks
+    clipboard: Option<Clipboard>, // opened on the first copy
 }
 
 impl Frontend {
-    pub fn new(bufsize: usize) -> Result<Frontend, Box<dyn Error>> {
+    pub fn new(config: &Config, source: DataSource, client: Client, latest: Option<Latest>) -> Result<Frontend, Box<dyn Error>> {
+        // the demo's random speeds would beat every real record
+        let records = match (&source, Records::path()) {
+            (DataSource::Api(_), Some(path)) => Some((state::load::<Records>(&path)?, path)),
+            _ => None,
+        };
+        // and the demo shouldn't replace a real journey's history
+        let saved_history = match (&source, history::path()) {
+            // unlike the records it's only a cache, a broken file gets overwritten soon
+            (DataSource::Api(_), Some(path)) if config.keep_history => Some((state::load(&path).unwrap_or_default(), path)),
+            _ => None,
+        };
+
         Ok(Frontend {
             selection: PanelSelection::BasicInformation,
-            data: VecDeque::with_capacity(bufsize),
+            labels: config.language.labels(),
+            keymap: config.keys.clone(),
+            selected_station: 0,
+            station_offset: 0,
+            selected_station_detailed: false,
+            wrap_stations: config.wrap_stations,
+            hide_passed: config.hide_passed,
+            connection_stops: true,
+            scrub: None,
+            search: None,
+            show_help: false,
+            on_arrival: config.on_arrival,
+            arrival_summary: None,
+            debug: None,
+            paused: false,
+            confirm_quit: config.confirm_quit,
+            confirming_quit: false,
+            sparkline: config.sparkline,
+            distance_graph: false,
+            graph_max_speed: config.graph_max_speed,
+            speed_colors: config.speed_colors,
+            units: config.units,
+            time_format: TimeFormat { clock: config.clock, zone: config.timezone },
+            delay_colors: config.delay_colors,
+            theme: config.theme,
+            queries: Worker::spawn(source, client),
+            data: VecDeque::with_capacity(config.history.max(1)),
+            history: config.history.max(1),
+            average_window: config.average_window,
+            last_update: Instant::now(),
+            trip_update: None,
+            trip_interval: config.trip_interval(),
+            redraw_interval: config.redraw_interval(),
+            error: None,
+            not_active: false,
+            notice: None,
+            fetching: false,
+            refresh_pending: false,
+            refresh_inflight: false,
+            approach_alert: config.approach_alert_km.map(|km| (km * 1000.0) as u64),
+            approach_alerted: None,
+            delay_alert: config.delay_alert_min,
+            delay_baseline: None,
+            delay_alerted: None,
+            alert_bell: config.alert_bell,
+            csv_log: config.log_csv.clone(),
+            log: config.log.clone(),
+            latest,
+            record_to_beat: records.as_ref().and_then(|(records, _)| records.top_speed.as_ref()).map(|top| top.speed),
+            records,
+            records_changed: false,
+            history_file: saved_history.as_ref().map(|(_, path)| path.clone()),
+            restore: saved_history.map(|(saved, _)| saved),
+            state_saved: Instant::now(),
+            panel_areas: Vec::new(),
+            clipboard: None,
         })
     }
 
+    // the one thing everybody wants to know, always visible
+    fn draw_next_stop(&self, frame: &mut Frame, area: Rect) {
+        let style = Style::new().fg(Color::Black).bg(Color::Cyan).bold();
+        frame.render_widget(Paragraph::new(self.next_stop_text()).style(style), area);
+    }
+
+    // "Nächster Halt: ... in ...km, an ..."
+    fn next_stop_text(&self) -> String {
+        let Some(info) = self.current() else { return String::new() };
+        let trip = &info.trip.trip;
+        let l = self.labels;
+
+        match trip.next_stop() {
+            Some(next) => {
+                let mut text = format!("{} {} {} {}", l.next_stop, next.station.name, l.next_stop_in,
+                    units::fmt_distance(next.distance_from(self.position()), self.units, l.numbers));
+                if let Some(arrival) = next.timetable.actualArrivalTime.or(next.timetable.scheduledArrivalTime) {
+                    text.push_str(&format!(", {} {}", l.next_stop_at, units::fmt_time(units::utc_time(arrival), self.time_format, false)));
+                }
+                text
+            }
+            // the portal may point past the stops it sends, e.g. at the end of a trip
+            None => format!("{} {}", l.next_stop, l.unknown),
+        }
+    }
+
+    // one line to send to whoever is waiting at the station
+    fn summary(&self) -> Option<String> {
+        let info = self.current()?;
+        let trip = &info.trip.trip;
+        let l = self.labels;
+
+        let mut parts = vec![format!("{} {}", trip.trainType, trip.vzn), self.next_stop_text()];
+        if let Some(delay) = trip.final_delay() {
+            parts.push(format!("{} {:+} min", l.delay_title, delay));
+        }
+        parts.push(units::fmt_speed(info.status.speed, self.units, l.numbers));
+        parts.push(units::fmt_coordinates(info.status.latitude, info.status.longitude));
+
+        Some(parts.join(" · "))
+    }
+
+    fn copy_summary(&mut self) {
+        let Some(summary) = self.summary() else { return };
+
+        // kept around, on X11 the clipboard is only filled as long as we own it
+        let result = match &mut self.clipboard {
+            Some(clipboard) => Ok(clipboard),
+            None => Clipboard::new().map(|clipboard| self.clipboard.insert(clipboard)),
+        }
+        .and_then(|clipboard| clipboard.set_text(summary.clone()));
+
+        match result {
+            Ok(()) => self.notify(format!("{} {}", self.labels.copied, summary)),
+            Err(e) => self.notify(format!("{} {}", self.labels.copy_failed, e)),
+        }
+    }
+
+    fn current(&self) -> Option<&Info> {
+        shown(&self.data, self.scrub)
+    }
+
+    // where the train should be by now, going on with the last known speed since the update;
+    // display only, the next update snaps back to the portal's value
+    fn position(&self) -> u64 {
+        let Some(info) = self.current() else { return 0 };
+        if self.paused || self.scrub.is_some() {
+            return info.trip.trip.actualPosition;
+        }
+
+        // every entry's position is as of its update, fetched or carried on
+        stats::extrapolated_position(info, self.last_update.elapsed().as_secs_f64())
+    }
+
+    // which way the last two positions point, "—" while standing
+    fn heading(&self) -> String {
+        let mut newest = self.data.iter().rev().take(2).map(|info| (info.status.latitude, info.status.longitude));
+        let (Some(to), Some(from)) = (newest.next(), newest.next()) else { return String::from("—") };
+        if from == to {
+            return String::from("—");
+        }
+
+        let sector = ((bearing(from, to) + 22.5) / 45.0) as usize % 8;
+        format!("{} {}", ["↑", "↗", "→", "↘", "↓", "↙", "←", "↖"][sector], self.labels.compass[sector])
+    }
+
     fn draw_basic_info(&self, frame: &mut Frame, area: Rect) {
-        let info = self.data.back().expect("Nothing to draw");
+        let info = self.current().expect("Nothing to draw");
 
-        let content = format!("\
-Schienenfahrzeugtyp:           {}
-Schienenfahrzeugbezeichnung:   {}
-Sozioökonomisches Milieu:      {}
-Streckenführung:               von {} nach {}
-", info.status.trainType, info.status.tzn, info.status.wagonClass,
-info.trip.trip.stops.first().expect("Everything has to start somewhere").station.name,
-info.trip.trip.stops.last().expect("Everything has to end somewhere").station.name);
+        let l = self.labels;
+        let content = aligned(&[
+            (l.train_type, match info.status.series_name() {
+                Some(name) => format!("{} ({}, BR {})", info.status.trainType, name, info.status.series),
+                None if !info.status.series.is_empty() => format!("{} (BR {})", info.status.trainType, info.status.series),
+                None => info.status.trainType.clone(),
+            }),
+            (l.train_id, info.status.tzn.clone()),
+            (l.wagon_class, info.status.wagonClass.clone()),
+            (l.service_level, {
+                // unknown levels as the portal calls them
+                let level = match info.status.serviceLevel.as_str() {
+                    "AVAILABLE_SERVICE" => l.service_available,
+                    "PARTIAL_SERVICE" => l.service_partial,
+                    "NO_SERVICE" => l.service_none,
+                    "" => l.unknown,
+                    other => other,
+                };
+                if info.status.bapInstalled { format!("{} · {}", level, l.bap_installed) } else { level.to_owned() }
+            }),
+            (l.route, format!("{} {} {} {}", l.from,
+                info.trip.trip.stops.first().expect("Everything has to start somewhere").station.name, l.to,
+                info.trip.trip.stops.last().expect("Everything has to end somewhere").station.name)),
+        ]);
 
         let block = if self.selection == PanelSelection::BasicInformation {
-            Block::bordered().title("Grundlegende Informationen").border_style(Color::Magenta)
+            Block::bordered().title(l.basic_title).border_style(self.theme.focused_border)
         } else {
-            Block::bordered().title("Grundlegende Informationen")
+            Block::bordered().title(l.basic_title)
         };
 
         frame.render_widget(Paragraph::new(content).block(block), area);
     }
 
     fn draw_status(&self, frame: &mut Frame, area: Rect) {
-        let info = self.data.back().expect("Nothing to draw");
+        let info = self.current().expect("Nothing to draw");
 
-        let ap = info.trip.trip.actualPosition;
+        let ap = self.position();
         let td = info.trip.trip.totalDistance;
+        let remaining = stats::remaining(info, ap);
+
+        let percent = |part: u64| stats::percent(part, td);
+        let average_speed = stats::average_speed(&self.data, self.average_window);
+
+        // portal's arrival time vs. what the average speed gives us
+        let final_stop = info.trip.trip.stops.last().expect("Everything has to end somewhere");
+        let planned_arrival = final_stop.timetable.actualArrivalTime.or(final_stop.timetable.scheduledArrivalTime)
+            .map(|time| units::fmt_time(units::utc_time(time), self.time_format, false))
+            .unwrap_or_else(|| String::from("-"));
+        let estimated_arrival = match stats::travel_seconds(remaining, average_speed) {
+            Some(seconds) => units::fmt_time(Utc::now() + TimeDelta::seconds(seconds as i64), self.time_format, false),
+            None => String::from("-"),
+        };
 
-        let average_speed = self.data.iter().fold(0.0, |acc, e| acc + e.status.speed) / self.data.len() as f64;
+        let next_stop = info.trip.trip.next_stop();
+        let countdown = match stats::next_stop_seconds(info, ap) {
+            Some(seconds) => format!("~{} min {} s", seconds / 60, seconds % 60),
+            None => String::from("—"),
+        };
+
+        // count down locally between updates
+        let connectivity = &info.status.connectivity;
+        let forecast = match (&connectivity.nextState, connectivity.remainingTimeSeconds) {
+            (Some(next), Some(remaining)) => format!("{} → {} in {}s", connectivity.currentState, next,
+                remaining.saturating_sub(self.last_update.elapsed().as_secs())),
+            _ => connectivity.currentState.clone(),
+        };
 
-        let content = format!("\
-Aktuelle Geschwindigkeit:      {:.0}km/h
-   Gleitender Mittelwert:      {:.0}km/h
-Internetzwerkverbindungsgüte:  {}
-Gesamte Streckenlänge:         {}km
-Davon bereits zurückgelegt:    {}km ({:.2}%)
-Verbleibend (nach Adam Riese): {}km ({:.2}%)
-Entfernung zum nächsten Halt:  {}km ({})
-Aktuelle geographische Lage:   ({:.03}N, {:.03}W)",
-info.status.speed, average_speed, info.status.internet, td / 1000, ap / 1000, ap as f64 / td as f64 * 100.0,
-(td - ap) / 1000, (td - ap) as f64 / td as f64 * 100.0, 0, "NEXT STOP", info.status.latitude, info.status.longitude);
+        let l = self.labels;
+        let rows = [
+            (l.speed, match info.speed_estimated {
+                true => format!("~{} ({})", units::fmt_speed(info.status.speed, self.units, l.numbers), l.speed_estimated),
+                false => units::fmt_speed(info.status.speed, self.units, l.numbers),
+            }),
+            // with and without the time standing at stations, if that makes a difference
+            (l.average_speed, match stats::moving_average_speed(&self.data, self.average_window) {
+                Some(moving) if (moving - average_speed).abs() >= 0.5 => format!("{} · {} {}", units::fmt_speed(average_speed, self.units, l.numbers),
+                    l.average_moving, units::fmt_speed(moving, self.units, l.numbers)),
+                _ => units::fmt_speed(average_speed, self.units, l.numbers),
+            }),
+            (l.internet, String::new()),
+            (l.connectivity, forecast),
+            (l.total_distance, units::fmt_distance(td, self.units, l.numbers)),
+            (l.traveled, format!("{} ({}%)", units::fmt_distance(ap, self.units, l.numbers), units::fmt_number(percent(ap), 2, l.numbers))),
+            (l.remaining, format!("{} ({}%)", units::fmt_distance(remaining, self.units, l.numbers), units::fmt_number(percent(remaining), 2, l.numbers))),
+            (l.next_stop_distance, match next_stop {
+                Some(next) => format!("{} ({})", units::fmt_distance(next.distance_from(ap), self.units, l.numbers), next.station.name),
+                None => String::from(l.unknown),
+            }),
+            (l.next_stop_countdown, countdown),
+            (l.position, format!("{} {}", units::fmt_coordinates(info.status.latitude, info.status.longitude), self.heading())),
+            (l.arrival_final, format!("{} ({}) / ~{} ({})", planned_arrival, l.arrival_planned,
+                estimated_arrival, l.arrival_estimated)),
+            (l.gps, String::new()),
+        ];
+
+        // frozen coordinates: GPS dropout or really standing still?
+        let gps_color = match info.status.gpsStatus.as_str() {
+            "VALID" => Color::Green,
+            "" => Color::DarkGray,
+            _ => Color::Red,
+        };
+        // one line per row, the empty ones get their styled values by label
+        let mut lines: Vec<Line> = aligned(&rows).lines().map(|line| Line::from(line.to_owned())).collect();
+        let row = |label: &str| rows.iter().position(|(row_label, _)| *row_label == label);
+        if let Some(internet) = row(l.internet).and_then(|index| lines.get_mut(index)) {
+            internet.spans.extend(signal_meter(Signal::parse(&info.status.internet)));
+            internet.push_span(Span::raw(format!(" {}", info.status.internet)));
+        }
+        if let Some(gps) = row(l.gps).and_then(|index| lines.get_mut(index)) {
+            gps.push_span(Span::raw("● ").fg(gps_color));
+            gps.push_span(Span::raw(format!("{} · {} {}", info.status.gpsStatus, l.server_connection,
+                if info.status.connection { "✓" } else { "✗" })));
+        }
 
         let block = if self.selection == PanelSelection::StatusInformation {
-            Block::bordered().title("Statusinformation").border_style(Color::Magenta)
+            Block::bordered().title(l.status_title).border_style(self.theme.focused_border)
         } else {
-            Block::bordered().title("Statusinformation")
+            Block::bordered().title(l.status_title)
         };
 
-        frame.render_widget(Paragraph::new(content).block(block), area);
+        // progress bar in the last row
+        let inner = block.inner(area);
+        let [_, progress] = Layout::new(Direction::Vertical, [ Constraint::default(), Constraint::Length(1) ])
+            .areas(inner);
+        let gauge = LineGauge::default()
+            .ratio(percent(ap).clamp(0.0, 100.0) / 100.0)
+            .label(format!("{}% · {} {}", units::fmt_number(percent(ap), 1, l.numbers), units::fmt_distance(remaining, self.units, l.numbers), l.left))
+            .filled_style(Style::new().green());
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+        frame.render_widget(gauge, progress);
     }
 
     fn draw_speed_graph(&self, frame: &mut Frame, area: Rect) {
+        // everything below is in the display unit
+        let speeds: Vec<f64> = self.data.iter().map(|e| self.units.speed(e.status.speed)).collect();
+
+        let range = stats::speed_range(&speeds);
+
+        let number = |speed: f64| units::fmt_number(speed, 0, self.labels.numbers);
+        let estimated = |index: usize| self.data.get(index).is_some_and(|e| e.speed_estimated);
+        let current_estimated = self.data.back().is_some_and(|e| e.speed_estimated);
+        let stats = format!("{} {}{} · ⌀ {} · min {} · max {} {}", self.labels.speed_current,
+            if current_estimated { "~" } else { "" }, number(range.current), number(range.average), number(range.min), number(range.max), self.units.speed_unit());
+        let block = Block::bordered().title(self.labels.speed_title).title_bottom(stats);
         let block = if self.selection == PanelSelection::SpeedInformation {
-            Block::bordered().title("Geschwindigkeitsverlauf").border_style(Color::Magenta)
+            block.border_style(self.theme.focused_border)
         } else {
-            Block::bordered().title("Geschwindigkeitsverlauf")
+            block
+        };
+
+        // next multiple of 50 above the fastest sample, but don't zoom in too much at a standstill
+        let y_max = match self.graph_max_speed {
+            Some(kmh) => self.units.speed(kmh).max(1.0),
+            None => ((range.max / 50.0).ceil() * 50.0).max(100.0),
         };
 
+        // one bar per sample, the newest ones if they don't all fit
+        if self.sparkline {
+            let width = area.width.saturating_sub(2) as usize;
+            let bars: Vec<u64> = speeds.iter().skip(speeds.len().saturating_sub(width)).map(|&speed| speed as u64).collect();
+            frame.render_widget(Sparkline::default().block(block).data(&bars).max(y_max as u64).green(), area);
+            return;
+        }
+
+        // fill the width until the buffer is full
+        let x_max = speeds.len().saturating_sub(1).max(1) as f64;
+
+        // one label per 50km/h, spread out further if there are not enough rows
+        let rows = area.height.saturating_sub(2).max(1) as f64;
+        let mut step = 50.0;
+        while y_max / step + 1.0 > rows {
+            step *= 2.0;
+        }
+
+        // keep the leftmost columns free for the labels
+        const LABEL_WIDTH: f64 = 4.0;
+        let columns = (area.width.saturating_sub(2) as f64 - LABEL_WIDTH).max(1.0);
+        let x_min = -LABEL_WIDTH * x_max / columns;
+
         let canvas = Canvas::default()
             .block(block)
-            .x_bounds([0.0, self.data.capacity() as f64])
-            .y_bounds([0.0, 300.0])
+            .x_bounds([x_min, x_max])
+            .y_bounds([0.0, y_max])
             .paint(|ctx| {
-                for (xc, (curr, next)) in self.data.iter().zip(self.data.iter().skip(1)).enumerate() {
+                let mut tick = 0.0;
+                while tick <= y_max {
+                    ctx.draw(&widgets::canvas::Line { x1: 0.0, y1: tick, x2: x_max, y2: tick, color: Color::DarkGray });
+                    ctx.print(x_min, tick, Line::from(format!("{:>3}", tick)).dark_gray());
+                    tick += step;
+                }
+
+                ctx.layer();
+
+                if let Some(index) = self.scrub {
+                    ctx.draw(&widgets::canvas::Line { x1: index as f64, y1: 0.0, x2: index as f64, y2: y_max, color: Color::Yellow });
+                }
+
+                for (xc, (&curr, &next)) in speeds.iter().zip(speeds.iter().skip(1)).enumerate() {
                     ctx.draw(&widgets::canvas::Line {
                         x1: xc as f64,
-                        y1: curr.status.speed,
+                        y1: curr,
                         x2: xc as f64 + 1.0,
-                        y2: next.status.speed,
-                        color: if curr.status.speed >= next.status.speed { Color::Red } else { Color::Green }
+                        y2: next,
+                        // estimated from GPS, see tick
+                        color: match self.speed_colors {
+                            _ if estimated(xc + 1) => Color::Gray,
+                            SpeedColors::Speed => speed_color((curr + next) / 2.0 / y_max, self.theme.speed_scale),
+                            SpeedColors::Direction => if curr >= next { self.theme.graph_down } else { self.theme.graph_up },
+                        }
+                    });
+                }
+            });
+
+        frame.render_widget(canvas, area);
+    }
+
+    // flat while standing, steep while racing
+    fn draw_distance_graph(&self, frame: &mut Frame, area: Rect) {
+        let distances: Vec<f64> = self.data.iter().map(|e| self.units.distance(e.trip.trip.actualPosition)).collect();
+        let current = self.data.back().map_or(0, |info| info.trip.trip.actualPosition);
+
+        // just the stretch driven in the buffer, at least one unit high
+        let y_min = distances.iter().copied().fold(f64::INFINITY, f64::min);
+        let y_min = if y_min.is_finite() { y_min.floor() } else { 0.0 };
+        let y_max = distances.iter().copied().fold(y_min + 1.0, f64::max).ceil();
+        let x_max = distances.len().saturating_sub(1).max(1) as f64;
+
+        const LABEL_WIDTH: f64 = 5.0;
+        let columns = (area.width.saturating_sub(2) as f64 - LABEL_WIDTH).max(1.0);
+        let x_min = -LABEL_WIDTH * x_max / columns;
+
+        let block = Block::bordered().title(self.labels.distance_title)
+            .title_bottom(units::fmt_distance(current, self.units, self.labels.numbers));
+        let block = if self.selection == PanelSelection::SpeedInformation {
+            block.border_style(self.theme.focused_border)
+        } else {
+            block
+        };
+
+        let canvas = Canvas::default()
+            .block(block)
+            .x_bounds([x_min, x_max])
+            .y_bounds([y_min, y_max])
+            .paint(|ctx| {
+                ctx.print(x_min, y_max, Line::from(format!("{:>4}", y_max)).dark_gray());
+                ctx.print(x_min, y_min, Line::from(format!("{:>4}", y_min)).dark_gray());
+
+                ctx.layer();
+
+                if let Some(index) = self.scrub {
+                    ctx.draw(&widgets::canvas::Line { x1: index as f64, y1: y_min, x2: index as f64, y2: y_max, color: Color::Yellow });
+                }
+
+                for (xc, (&curr, &next)) in distances.iter().zip(distances.iter().skip(1)).enumerate() {
+                    ctx.draw(&widgets::canvas::Line { x1: xc as f64, y1: curr, x2: xc as f64 + 1.0, y2: next, color: Color::Cyan });
+                }
+            });
+
+        frame.render_widget(canvas, area);
+    }
+
+    // is it getting better or worse?
+    fn draw_delay_graph(&self, frame: &mut Frame, area: Rect) {
+        let delays: Vec<f64> = self.data.iter().map(|e| e.trip.trip.final_delay().unwrap_or(0) as f64).collect();
+        let current = delays.last().copied().unwrap_or(0.0);
+
+        // always include on time and a few minutes, early arrivals go below zero
+        let y_min = delays.iter().fold(0.0, |acc: f64, &d| acc.min(d));
+        let y_max = delays.iter().fold(5.0, |acc: f64, &d| acc.max(d));
+        let x_max = delays.len().saturating_sub(1).max(1) as f64;
+
+        const LABEL_WIDTH: f64 = 4.0;
+        let columns = (area.width.saturating_sub(2) as f64 - LABEL_WIDTH).max(1.0);
+        let x_min = -LABEL_WIDTH * x_max / columns;
+
+        let block = Block::bordered().title(self.labels.delay_title).title_bottom(format!("{:+} min", current));
+        let canvas = Canvas::default()
+            .block(block)
+            .x_bounds([x_min, x_max])
+            .y_bounds([y_min, y_max])
+            .paint(|ctx| {
+                ctx.draw(&widgets::canvas::Line { x1: 0.0, y1: 0.0, x2: x_max, y2: 0.0, color: Color::DarkGray });
+                ctx.print(x_min, y_max, Line::from(format!("{:>3}", y_max)).dark_gray());
+                ctx.print(x_min, 0.0, Line::from(format!("{:>3}", 0)).dark_gray());
+
+                ctx.layer();
+
+                // red when it grows, green when we catch up
+                for (xc, (&curr, &next)) in delays.iter().zip(delays.iter().skip(1)).enumerate() {
+                    ctx.draw(&widgets::canvas::Line {
+                        x1: xc as f64,
+                        y1: curr,
+                        x2: xc as f64 + 1.0,
+                        y2: next,
+                        color: if next > curr { self.theme.delay_bad } else if next < curr { self.theme.delay_ok } else { self.theme.delay_warn }
                     });
                 }
             });
@@ -147,151 +666,1129 @@ info.status.speed, average_speed, info.status.internet, td / 1000, ap / 1000, ap
     //     }
     // }
 
-    fn draw_trip(&self, frame: &mut Frame, area: Rect) {
-        let info = self.data.back().expect("Nothing to draw");
+    // scheduled time with the delay in minutes, "-" where there is none (no arrival at the first stop...)
+    fn planned_time(&self, scheduled: Option<u64>, actual: Option<u64>) -> String {
+        match (scheduled, actual) {
+            (Some(scheduled), Some(actual)) => {
+                let delay = stats::delay_minutes(scheduled, actual);
+                format!("{} ({:+})", units::fmt_time(units::utc_time(scheduled), self.time_format, false), delay)
+            }
+            (Some(scheduled), None) => units::fmt_time(units::utc_time(scheduled), self.time_format, false),
+            _ => String::from("-"),
+        }
+    }
+
+    fn draw_station_detail(&self, frame: &mut Frame, area: Rect, stop: &Stop, previous: Option<&Stop>) {
+        let timetable = &stop.timetable;
+        let arrival = self.planned_time(timetable.scheduledArrivalTime, timetable.actualArrivalTime);
+        let departure = self.planned_time(timetable.scheduledDepartureTime, timetable.actualDepartureTime);
+
+        let l = self.labels;
+        let track = if stop.track.actual == stop.track.scheduled {
+            stop.track.actual.clone()
+        } else {
+            format!("{} ({} {})", stop.track.actual, l.instead_of, stop.track.scheduled)
+        };
+
+        // the portal's own wording, next to the delays we compute
+        let mut rows = vec![(l.arrival, arrival), (l.departure, departure)];
+        let texts = [(l.arrival_delay, &timetable.arrivalDelay), (l.departure_delay, &timetable.departureDelay)];
+        rows.extend(texts.into_iter()
+            .filter_map(|(label, text)| text.as_ref().filter(|text| !text.is_empty()).map(|text| (label, text.clone()))));
+        rows.push((l.track, track));
+        // the portal's distance is the one from the previous stop, 0 at the first
+        if let Some(previous) = previous {
+            rows.push((l.segment, format!("{} {} {}", units::fmt_distance(stop.info.distance, self.units, l.numbers), l.since, previous.station.name)));
+        }
+
+        let mut lines: Vec<Line> = aligned(&rows)
+            .lines()
+            .map(|line| Line::from(line.to_owned()))
+            .collect();
+        lines.push(Line::from(""));
+
+        match &stop.delayReasons {
+            Some(reasons) if !reasons.is_empty() => {
+                lines.push(Line::from(l.delay_reasons));
+                lines.extend(reasons.iter().map(|reason| Line::from(format!(" • {}", reason.text))));
+            }
+            _ => lines.push(Line::from(l.no_delay_reasons)),
+        }
+
+        let block = Block::bordered().title(stop.station.name.clone());
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn draw_connection(&self, frame: &mut Frame, area: Rect, connection: &Connection) {
+        let l = self.labels;
+
+        let number = connection.vzn.as_ref().or(connection.trainNumber.as_ref());
+        let train = match (connection.trainType.as_ref(), number) {
+            (Some(train_type), Some(number)) => format!("{} {}", train_type, number),
+            (train_type, number) => train_type.or(number).cloned().unwrap_or_default(),
+        };
+
+        let mut departure = connection.station.as_ref().map(|station| station.name.clone()).unwrap_or_default();
+        if let Some(timetable) = &connection.timetable {
+            if timetable.scheduledDepartureTime.is_some() {
+                departure.push_str(&format!(" {}", self.planned_time(timetable.scheduledDepartureTime, timetable.actualDepartureTime)));
+            }
+        }
+        if let Some(track) = &connection.track {
+            departure.push_str(&format!(", {} {}", l.track, track.actual));
+        }
 
-        let lphk = 5; // lines per kilometers (TODO calculate appropriate value)
+        let mut lines: Vec<Line> = aligned(&[(l.connection_train, train), (l.connection_departure, departure)])
+            .lines()
+            .map(|line| Line::from(line.to_owned()))
+            .collect();
+        if connection.is_conflict() {
+            lines.push(Line::from(l.connection_conflict).red().bold());
+        }
 
-        let height = (area.height - 2) as usize; // subtract 2 for border
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(l.connection_title)), area);
+    }
 
-        let (mut miny, mut maxy, mut minx, mut maxx) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
-        for stop in &info.trip.trip.stops {
-            miny = stop.station.geocoordinates.latitude.min(miny);
-            maxy = stop.station.geocoordinates.latitude.max(maxy);
-            minx = stop.station.geocoordinates.longitude.min(minx);
-            maxx = stop.station.geocoordinates.longitude.max(maxx);
+    // one line in the station list, the name cut short so the rest fits into `width` columns
+    fn station_label(&self, stop: &Stop, width: usize) -> Vec<Span<'static>> {
+        let full = self.station_label_named(stop, &stop.station.name);
+        let excess = Line::from(full.clone()).width().saturating_sub(width);
+        if excess == 0 {
+            return full;
         }
 
-        let data_when: DateTime<Local> = DateTime::from_timestamp(info.status.serverTime as i64, 0).unwrap().into();
-        let now = Local::now().time();
-        let diff = now - data_when.time();
+        let name_width = text::width(&stop.station.name).saturating_sub(excess).max(1);
+        self.station_label_named(stop, &ellipsize(&stop.station.name, name_width))
+    }
+
+    fn station_label_named(&self, stop: &Stop, name: &str) -> Vec<Span<'static>> {
+        let mut spans = self.station_time_label(stop, name);
+
+        // easy to miss, and then so is the train
+        let track = &stop.track;
+        if !track.scheduled.is_empty() && track.actual != track.scheduled {
+            let l = self.labels;
+            spans.push(Span::raw(format!(" ⚠ {} {} ({} {})", l.track.trim_end_matches(':'), track.actual, l.instead_of, track.scheduled))
+                .red().bold());
+        }
+
+        spans
+    }
+
+    // name, arrival and how late we are
+    fn station_time_label(&self, stop: &Stop, name: &str) -> Vec<Span<'static>> {
+        if let Some(sat) = stop.timetable.scheduledArrivalTime {
+            let time = units::fmt_time(units::utc_time(sat), self.time_format, false);
+            // no actual time (yet) means no known delay
+            let aat = stop.timetable.actualArrivalTime.unwrap_or(sat);
+            let delay = stats::delay_minutes(sat, aat);
+
+            let delay_mood = match delay {
+                -1000..0 => "🤨",
+                0..1 => "😁",
+                1..2 => "😄",
+                2..4 => "😃",
+                4..6 => "😀",
+                6..9 => "🤔",
+                9..13 => "🫠",
+                13..18 => "🥲",
+                18..30 => "😨",
+                30..40 => "🫢",
+                40..60 => "😬",
+                60..80 => "🫨",
+                80..100 => "🤮",
+                100..120 => "🤯",
+                120..140 => "🤬",
+                _ => "💀",
+            };
+
+            // weight as well as color, for those who can't tell the colors apart
+            let emphasize = self.theme.emphasize_delays;
+            let style = if delay >= self.delay_colors.red {
+                let style = Style::new().fg(self.theme.delay_bad);
+                if emphasize { style.bold().underlined() } else { style }
+            } else if delay >= self.delay_colors.yellow {
+                let style = Style::new().fg(self.theme.delay_warn);
+                if emphasize { style.bold() } else { style }
+            } else {
+                Style::new().fg(self.theme.delay_ok)
+            };
+
+            // on time shows no number, the time itself is green then
+            if delay == 0 {
+                vec![Span::raw(format!("{} (", name)), Span::styled(time, style), Span::raw(")")]
+            } else {
+                vec![
+                    Span::raw(format!("{} ({}; ", name, time)),
+                    Span::styled(format!("{}{}{}", if delay < 0 { "-" } else { "+" }, delay.abs(), delay_mood), style),
+                    Span::raw(")"),
+                ]
+            }
+        } else {
+            vec![Span::raw(format!("{} (-)", name))]
+        }
+    }
+
+    // passed stops fade out, the next one stands out, with the route line in the left margin
+    fn stop_line(&self, stop: &Stop, next_stop_eva: Option<&str>, selected: bool, width: usize) -> Line<'static> {
+        let next = Some(stop.station.evaNr.as_str()) == next_stop_eva;
+        let style = if stop.info.passed {
+            Style::new().dark_gray()
+        } else if next {
+            Style::new().fg(self.theme.next_station).bold()
+        } else {
+            Style::new()
+        };
+        let style = if selected { style.reversed() } else { style };
+
+        // green as far as we got, half way out of the stop just left, ringed while standing there
+        let marker = match stop.info.position() {
+            StopPosition::Passed => Span::raw("● ").fg(self.theme.passed_station),
+            StopPosition::Departed => Span::raw("◐ ").fg(self.theme.passed_station),
+            StopPosition::Arrived => Span::raw("◉ ").fg(self.theme.next_station),
+            StopPosition::Future if next => Span::raw("○ ").fg(self.theme.next_station),
+            StopPosition::Future => Span::raw("○ ").dark_gray(),
+        };
+
+        let mut spans = vec![marker];
+        spans.extend(self.station_label(stop, width).into_iter().map(|span| span.patch_style(style)));
+        Line::from(spans)
+    }
+
+    // the connecting train's route, as far as there is room
+    fn draw_connection_stops(&self, frame: &mut Frame, area: Rect, stops: &[Stop], next_stop_eva: Option<&str>) {
+        let height = area.height.saturating_sub(2) as usize;
+        let width = (area.width as usize).saturating_sub(2 + 2);
+        let lines: Vec<Line> = stops.iter()
+            .take(height)
+            .map(|stop| self.stop_line(stop, next_stop_eva, false, width))
+            .collect();
+
+        let block = Block::bordered().title(self.labels.connection_stops_title);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn draw_trip(&mut self, frame: &mut Frame, area: Rect) {
+        let info = shown(&self.data, self.scrub).expect("Nothing to draw");
+        let stops = &info.trip.trip.stops;
+        self.selected_station = self.selected_station.min(stops.len().saturating_sub(1)); // scrubbing may change the route
+
+        // a hidden stop can't stay selected, go on to the next listed one
+        let listed = listed(stops, self.hide_passed);
+        if !listed.contains(&self.selected_station) {
+            self.selected_station = listed.iter().copied().find(|&i| i > self.selected_station)
+                .or(listed.last().copied())
+                .unwrap_or(0);
+        }
+        let cursor_row = listed.iter().position(|&i| i == self.selected_station).unwrap_or(0);
+
+        let data_when = units::utc_time(info.status.serverTime);
+        let diff = Utc::now() - data_when;
+
+        let l = self.labels;
+        let ago = format!("{}{}{}", l.seconds_ago_prefix, diff.num_seconds(), l.seconds_ago_suffix);
+        let mut last_update = Line::from(vec![
+            Span::raw(format!("[{} {} (", l.last_update, units::fmt_time(data_when, self.time_format, true))),
+            if diff > STALE_AFTER { Span::raw(ago).red().bold() } else { Span::raw(ago) },
+            Span::raw(")]"),
+        ]);
+        if self.paused {
+            last_update.push_span(Span::raw(format!("[{}]", l.paused)));
+        }
+        if self.scrub.is_some() {
+            last_update.push_span(Span::raw(format!("[{}]", l.scrubbing)).yellow());
+        }
 
         let block = if self.selection == PanelSelection::TripInformation {
-            Block::bordered().title("Streckenverlauf").border_style(Color::Magenta)
-                .title_bottom(format!("[Zuletzt aktualisiert: {} (vor {} Sekunden)]", data_when.format("%H:%M:%S"), diff.num_seconds()))
+            Block::bordered().title(l.trip_title).border_style(self.theme.focused_border)
+                .title_bottom(last_update)
+        } else {
+            Block::bordered().title(l.trip_title)
+                .title_bottom(last_update)
+        };
+
+        // onward connection below the list, if there is one
+        let connection = &info.trip.connection;
+        let area = if connection.is_known() {
+            let height = if connection.is_conflict() { 5 } else { 4 };
+            let [list, below] = Layout::new(Direction::Vertical, [ Constraint::default(), Constraint::Length(height) ])
+                .areas(area);
+            self.draw_connection(frame, below, connection);
+            list
+        } else {
+            area
+        };
+
+        // the onward journey next to this one
+        let area = match connection.stops.as_deref() {
+            Some(onward) if self.connection_stops && !onward.is_empty() => {
+                let [list, right] = Layout::new(Direction::Horizontal, [ Constraint::Percentage(50), Constraint::Percentage(50) ])
+                    .areas(area);
+                let next_stop_eva = connection.info.as_ref().map(|info| info.actualNext.as_str());
+                self.draw_connection_stops(frame, right, onward, next_stop_eva);
+                list
+            }
+            _ => area,
+        };
+
+        // detailed view of the selected station next to the list
+        let area = if self.selected_station_detailed {
+            let [list, detail] = Layout::new(Direction::Horizontal, [ Constraint::default(), Constraint::Length(45) ])
+                .areas(area);
+            let previous = self.selected_station.checked_sub(1).map(|index| &stops[index]);
+            self.draw_station_detail(frame, detail, &stops[self.selected_station], previous);
+            list
+        } else {
+            area
+        };
+
+        // nothing fits inside the border (tiny terminal, or while resizing)
+        if area.width <= 2 || area.height <= 2 {
+            frame.render_widget(block, area);
+            return;
+        }
+
+        // the search input takes the last row
+        let height = area.height.saturating_sub(2) as usize; // subtract 2 for border
+        let height = height.saturating_sub(usize::from(self.search.is_some()));
+
+        // scroll just enough to keep the selection visible, without empty rows at the end
+        if cursor_row < self.station_offset {
+            self.station_offset = cursor_row;
+        } else if cursor_row >= self.station_offset + height {
+            self.station_offset = cursor_row + 1 - height;
+        }
+        self.station_offset = self.station_offset.min(listed.len().saturating_sub(height));
+
+        // spread the stops over the rows if there is room, the gaps continue the route line
+        let gaps = listed.len().saturating_sub(1);
+        let spare = if gaps > 0 && listed.len() < height {
+            (height - listed.len()).min(gaps * MAX_STATION_GAP)
+        } else {
+            0
+        };
+        // rounded so the spare rows are shared out evenly
+        let gap_after = |i: usize| if i < gaps { (i + 1) * spare / gaps - i * spare / gaps } else { 0 };
+
+        // inside the border and next to the route marker
+        let width = (area.width as usize).saturating_sub(2 + 2);
+
+        let next_stop_eva = info.trip.trip.next_stop().map(|stop| stop.station.evaNr.as_str());
+        // the cursor is shown while it can be moved or its details are open
+        let cursor = self.selection == PanelSelection::TripInformation || self.selected_station_detailed;
+        let lines: Vec<Line> = listed.iter()
+            .enumerate()
+            .skip(self.station_offset)
+            .take(height)
+            .flat_map(|(row, &i)| {
+                let stop = &stops[i];
+                let line = self.stop_line(stop, next_stop_eva, cursor && i == self.selected_station, width);
+
+                // leaving a passed stop means that part of the line is (being) driven
+                let segment = if stop.info.passed { Span::raw("│").fg(self.theme.passed_station) } else { Span::raw("│").dark_gray() };
+                iter::once(line).chain(iter::repeat_n(Line::from(segment), gap_after(row)))
+            })
+            .collect();
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+
+        if let Some((query, _)) = &self.search {
+            let input = Rect::new(area.x + 1, area.y + area.height.saturating_sub(2), area.width.saturating_sub(2), 1);
+            frame.render_widget(Paragraph::new(format!("{} {}_", l.search_prompt, query)), input);
+        }
+    }
+
+    fn current_notice(&self) -> Option<&str> {
+        self.notice.as_ref()
+            .filter(|(_, when)| when.elapsed() < NOTICE_DURATION)
+            .map(|(notice, _)| notice.as_str())
+    }
+
+    // the selected stop after the approach alert went off for it, until the train is past it
+    fn approaching(&self) -> Option<(&Stop, u64)> {
+        let alerted = self.approach_alerted.as_ref()?;
+        let trip = &self.data.back()?.trip.trip;
+        let stop = trip.stops.get(self.selected_station).filter(|stop| &stop.station.evaNr == alerted && !stop.info.passed)?;
+        Some((stop, stop.distance_from(trip.actualPosition)))
+    }
+
+    fn current_delay_alert(&self) -> Option<(i64, i64)> {
+        self.delay_alerted
+            .filter(|(_, _, when)| when.elapsed() < DELAY_ALERT_DURATION)
+            .map(|(from, to, _)| (from, to))
+    }
+
+    // not missing the stop beats everything, then a grown delay, then errors take precedence over notices
+    fn draw_banner(&self, frame: &mut Frame, area: Rect) {
+        if let Some((stop, distance)) = self.approaching() {
+            // flashes along with the redraws
+            let mut style = Style::new().fg(Color::Black).bg(self.theme.next_station).bold();
+            if Utc::now().timestamp_millis() / 500 % 2 == 1 {
+                style = style.reversed();
+            }
+            let text = format!("{} {} {} {}", self.labels.approaching, stop.station.name, self.labels.next_stop_in,
+                units::fmt_distance(distance, self.units, self.labels.numbers));
+            frame.render_widget(Paragraph::new(text).style(style), area);
+        } else if let Some((from, to)) = self.current_delay_alert() {
+            let style = Style::new().fg(Color::White).bg(self.theme.delay_bad).bold();
+            let text = format!("{} +{} → +{} min", self.labels.delay_grown, from, to);
+            frame.render_widget(Paragraph::new(text).style(style), area);
+        } else if let Some(error) = &self.error {
+            let style = Style::new().fg(Color::White).bg(Color::Red);
+            frame.render_widget(Paragraph::new(format!("{} {}", self.labels.update_failed, error)).style(style), area);
+        } else if let Some(notice) = self.current_notice() {
+            let style = Style::new().fg(Color::Black).bg(Color::Green);
+            frame.render_widget(Paragraph::new(notice).style(style), area);
+        } else if let Some(conflict) = self.conflict() {
+            let style = Style::new().fg(Color::White).bg(Color::Red).bold();
+            frame.render_widget(Paragraph::new(conflict).style(style), area);
+        } else if self.is_stale() {
+            let style = Style::new().fg(Color::Black).bg(Color::Yellow);
+            frame.render_widget(Paragraph::new(self.labels.stale_data).style(style), area);
+        }
+    }
+
+    // the onward connection is at risk, with the portal's reason made readable
+    fn conflict(&self) -> Option<String> {
+        let connection = &self.data.back()?.trip.connection;
+        if !connection.is_conflict() {
+            return None;
+        }
+
+        // codes look like SOME_REASON
+        let reason = format!("({})", connection.conflict.to_lowercase().replace('_', " "));
+        let parts = [Some("⚠"), Some(self.labels.connection_conflict), connection.trainType.as_deref(),
+            connection.trainNumber.as_deref(), Some(reason.as_str())];
+        Some(parts.iter().flatten().copied().collect::<Vec<_>>().join(" "))
+    }
+
+    // the newest data is too old to be trusted, even if fetching it worked
+    fn is_stale(&self) -> bool {
+        self.data.back()
+            .is_some_and(|info| Utc::now() - units::utc_time(info.status.serverTime) > STALE_AFTER)
+    }
+
+    fn draw_map(&self, frame: &mut Frame, area: Rect) {
+        let info = self.current().expect("Nothing to draw");
+
+        let stations: Vec<(f64, f64)> = info.trip.trip.stops.iter()
+            .map(|stop| (stop.station.geocoordinates.longitude, stop.station.geocoordinates.latitude))
+            .collect();
+        let position = (info.status.longitude, info.status.latitude);
+
+        // fit the whole route and the train, with some margin
+        let (mut minx, mut maxx, mut miny, mut maxy) = (position.0, position.0, position.1, position.1);
+        for &(x, y) in &stations {
+            minx = x.min(minx);
+            maxx = x.max(maxx);
+            miny = y.min(miny);
+            maxy = y.max(maxy);
+        }
+        let margin_x = ((maxx - minx) * 0.1).max(0.1);
+        let margin_y = ((maxy - miny) * 0.1).max(0.1);
+
+        let block = if self.selection == PanelSelection::MapInformation {
+            Block::bordered().title(self.labels.map_title).border_style(self.theme.focused_border)
         } else {
-            Block::bordered().title("Streckenverlauf")
-                .title_bottom(format!("[Zuletzt aktualisiert: {} (vor {} Sekunden)]", data_when.format("%H:%M:%S"), diff.num_seconds()))
+            Block::bordered().title(self.labels.map_title)
         };
 
         let canvas = Canvas::default()
             .block(block)
-            .x_bounds([minx, maxx])
-            .y_bounds([miny, maxy])
+            .x_bounds([minx - margin_x, maxx + margin_x])
+            .y_bounds([miny - margin_y, maxy + margin_y])
             .paint(|ctx| {
-                for (curr, next) in info.trip.trip.stops.iter().zip(info.trip.trip.stops.iter().skip(1)) {
-                    ctx.draw(&widgets::canvas::Line {
-                        x1: curr.station.geocoordinates.longitude,
-                        y1: curr.station.geocoordinates.latitude,
-                        x2: next.station.geocoordinates.longitude,
-                        y2: next.station.geocoordinates.latitude,
-                        color: Color::White,
-                    });
+                ctx.draw(&Map { resolution: MapResolution::High, color: Color::DarkGray });
+                ctx.layer();
 
-                    let text = if let Some(sat) = curr.timetable.scheduledArrivalTime {
-                        let time: DateTime<Local> = DateTime::from_timestamp(sat as i64 / 1000, 0).unwrap().into();
-                        let now = Local::now().time();
-                        let aat = curr.timetable.actualArrivalTime.expect("If there is a scheduled time there should also be an actual time");
-                        let delay = (aat as i64 - sat as i64) / 1000 / 60;
-
-                        let delay_mood = match delay {
-                            -1000..0 => "🤨",
-                            0..1 => "😁",
-                            1..2 => "😄",
-                            2..4 => "😃",
-                            4..6 => "😀",
-                            6..9 => "🤔",
-                            9..13 => "🫠",
-                            13..18 => "🥲",
-                            18..30 => "😨",
-                            30..40 => "🫢",
-                            40..60 => "😬",
-                            60..80 => "🫨",
-                            80..100 => "🤮",
-                            100..120 => "🤯",
-                            120..140 => "🤬",
-                            _ => "💀",
-                        };
-
-                        if delay == 0 {
-                            format!("{} ({})", curr.station.name.clone(), time.format("%H:%M"))
-                        } else {
-                            format!("{} ({}; {}{}{})", curr.station.name.clone(), time.format("%H:%M"),
-                            if delay < 0 { "-" } else { "+" }, delay, delay_mood)
-                        }
-                    } else {
-                        format!("{} (-)", curr.station.name.clone())
-                    };
+                // straight lines between the stops, green as far as we got
+                let stops = &info.trip.trip.stops;
+                let driven = self.position();
+                for (from, to) in stops.iter().zip(stops.iter().skip(1)) {
+                    let (x1, y1) = (from.station.geocoordinates.longitude, from.station.geocoordinates.latitude);
+                    let (x2, y2) = (to.station.geocoordinates.longitude, to.station.geocoordinates.latitude);
+                    let (start, end) = (from.info.distanceFromStart, to.info.distanceFromStart);
 
-                    ctx.print(curr.station.geocoordinates.longitude, curr.station.geocoordinates.latitude, Line::from(text));
+                    if driven >= end {
+                        ctx.draw(&widgets::canvas::Line { x1, y1, x2, y2, color: self.theme.passed_station });
+                    } else if driven <= start {
+                        ctx.draw(&widgets::canvas::Line { x1, y1, x2, y2, color: Color::Gray });
+                    } else {
+                        let fraction = (driven - start) as f64 / (end - start) as f64;
+                        let (xs, ys) = (x1 + (x2 - x1) * fraction, y1 + (y2 - y1) * fraction);
+                        ctx.draw(&widgets::canvas::Line { x1, y1, x2: xs, y2: ys, color: self.theme.passed_station });
+                        ctx.draw(&widgets::canvas::Line { x1: xs, y1: ys, x2, y2, color: Color::Gray });
+                    }
                 }
 
-                ctx.draw(&Circle {
-                    x: info.trip.trip.stops[3].station.geocoordinates.longitude,
-                    y: info.trip.trip.stops[3].station.geocoordinates.latitude,
-                    radius: 0.01,
-                    color: Color::Red,
-                });
+                ctx.draw(&Points { coords: &stations, color: Color::Yellow });
+                ctx.draw(&Circle { x: position.0, y: position.1, radius: margin_y / 2.0, color: Color::Red });
             });
 
         frame.render_widget(canvas, area);
     }
 
-    fn ui(&self, frame: &mut Frame) {
-        let layout = Layout::new(Direction::Vertical, [ Constraint::Length(6), Constraint::Length(10), Constraint::default() ])
-            .split(frame.size());
+    // modal on top of everything else
+    fn draw_help(&self, frame: &mut Frame, area: Rect) {
+        let l = self.labels;
+
+        let keys: Vec<(&str, String)> = Action::ALL.iter()
+            .map(|&action| {
+                let keys: Vec<String> = self.keymap.keys(action).into_iter().map(keymap::key_name).collect();
+                (l.action(action), keys.join(", "))
+            })
+            .collect();
+        let panels = [
+            (l.basic_title, l.help_basic.to_owned()),
+            (l.status_title, l.help_status.to_owned()),
+            (l.speed_title, l.help_speed.to_owned()),
+            (l.trip_title, l.help_trip.to_owned()),
+            (l.map_title, l.help_map.to_owned()),
+        ];
+
+        let mut lines = vec![Line::from(l.help_keys).bold()];
+        lines.extend(aligned(&keys).lines().map(|line| Line::from(format!("  {}", line))));
+        lines.push(Line::from(""));
+        lines.push(Line::from(l.help_panels).bold());
+        lines.extend(aligned(&panels).lines().map(|line| Line::from(format!("  {}", line))));
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+        let popup = centered(area, width, lines.len() as u16 + 2);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(l.help_title).border_style(self.theme.focused_border)), popup);
+    }
+
+    // everything the portal sent, as parsed
+    fn draw_debug(&mut self, frame: &mut Frame, area: Rect, offset: u16) {
+        let text = format!("{:#?}", self.current());
+        // don't scroll past the end, or scrolling back would seem stuck
+        let offset = offset.min(text.lines().count().saturating_sub(1) as u16);
+        self.debug = Some(offset);
+
+        let block = Block::bordered().title(self.labels.debug_title).border_style(self.theme.focused_border);
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(text).scroll((offset, 0)).block(block), area);
+    }
+
+    fn draw_arrival_summary(&self, frame: &mut Frame, area: Rect, summary: &str) {
+        let width = summary.lines().map(text::width).max().unwrap_or(0) as u16 + 2;
+        let popup = centered(area, width, summary.lines().count() as u16 + 2);
+        let block = Block::bordered().title(self.labels.arrived_title).border_style(self.theme.focused_border);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(summary.to_owned()).block(block), popup);
+    }
+
+    fn draw_quit_confirm(&self, frame: &mut Frame, area: Rect) {
+        let text = self.labels.quit_question;
+        let block = Block::bordered().title(self.labels.quit_title).border_style(self.theme.focused_border);
+
+        let popup = centered(area, text::width(text) as u16 + 4, 3);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(text).centered().block(block), popup);
+    }
+
+    fn draw_no_trip(&self, frame: &mut Frame, area: Rect) {
+        let text = self.labels.no_trip;
+        let block = Block::bordered().title(self.labels.trip_title);
+
+        // text plus border
+        let popup = centered(area, text::width(text) as u16 + 4, 3);
+        frame.render_widget(Paragraph::new(text).centered().block(block), popup);
+    }
+
+    // top right, over whatever is there, so nothing moves when it starts or stops
+    fn draw_spinner(&self, frame: &mut Frame) {
+        let size = frame.size();
+        if size.width < 2 || size.height == 0 {
+            return;
+        }
+        let glyph = if self.fetching {
+            SPINNER[(Utc::now().timestamp_millis() / 100) as usize % SPINNER.len()]
+        } else {
+            SPINNER_IDLE
+        };
+        frame.render_widget(Paragraph::new(glyph), Rect::new(size.x + size.width - 2, size.y, 1, 1));
+    }
+
+    fn ui(&mut self, frame: &mut Frame) {
+        let banner_height = if self.approaching().is_some() || self.current_delay_alert().is_some() || self.error.is_some() || self.current_notice().is_some() || self.conflict().is_some() || self.is_stale() { 1 } else { 0 };
+        let [banner, area] = Layout::new(Direction::Vertical, [ Constraint::Length(banner_height), Constraint::default() ])
+            .areas(frame.size());
+
+        self.panel_areas.clear();
+
+        self.draw_banner(frame, banner);
+
+        // not on a train (or the portal doesn't know about the trip), nothing received yet
+        if self.not_active {
+            self.draw_no_trip(frame, area);
+        } else if self.data.is_empty() {
+            frame.render_widget(Paragraph::new(self.labels.waiting).block(Block::bordered()), area);
+        } else {
+            self.draw_panels(frame, area);
+        }
+
+        self.draw_spinner(frame);
+
+        if let Some(offset) = self.debug {
+            self.draw_debug(frame, area, offset);
+        }
+        if self.show_help {
+            self.draw_help(frame, area);
+        }
+        if let Some(summary) = &self.arrival_summary {
+            self.draw_arrival_summary(frame, area, summary);
+        }
+        if self.confirming_quit {
+            self.draw_quit_confirm(frame, area);
+        }
+    }
+
+    // the most useful keys for the focused panel, as they are bound
+    fn draw_hints(&self, frame: &mut Frame, area: Rect) {
+        let l = self.labels;
+        let mut hints = vec![(vec![Action::NextPanel], l.hint_panel)];
+        match self.selection {
+            PanelSelection::TripInformation => {
+                hints.push((vec![Action::StationDown, Action::StationUp], l.hint_station));
+                hints.push((vec![Action::ToggleDetail], l.hint_details));
+                hints.push((vec![Action::Search], l.hint_search));
+                hints.push((vec![Action::HidePassed], l.hint_hide_passed));
+            }
+            PanelSelection::SpeedInformation => {
+                hints.push((vec![Action::ToggleSparkline], l.hint_sparkline));
+                hints.push((vec![Action::ToggleGraph], l.hint_graph));
+                hints.push((vec![Action::ToggleUnits], l.hint_units));
+            }
+            _ => (),
+        }
+        if self.scrub.is_some() {
+            hints.push((vec![Action::ScrubBack, Action::ScrubForward], l.hint_scrub));
+        } else {
+            hints.push((vec![Action::Scrub], l.hint_scrub));
+        }
+        hints.push((vec![Action::Help], l.hint_help));
+        hints.push((vec![Action::Quit], l.hint_quit));
+
+        // first key of each action, unbound ones are left out, and as many whole hints as fit
+        let mut room = area.width as usize;
+        let spans: Vec<Span> = hints.into_iter()
+            .filter_map(|(actions, hint)| {
+                let keys: Vec<String> = actions.iter()
+                    .filter_map(|&action| self.keymap.keys(action).first().map(|&key| keymap::key_name(key)))
+                    .collect();
+                (!keys.is_empty()).then(|| format!("{}: {}  ", keys.join("/"), hint))
+            })
+            .take_while(|hint| {
+                let fits = text::width(hint.trim_end()) <= room;
+                room = room.saturating_sub(text::width(hint));
+                fits
+            })
+            .map(Span::raw)
+            .collect();
+
+        frame.render_widget(Paragraph::new(Line::from(spans)).dark_gray(), area);
+    }
+
+    fn draw_panels(&mut self, frame: &mut Frame, area: Rect) {
+        let compact = area.width < COMPACT_WIDTH || area.height < COMPACT_HEIGHT;
+        let [next_stop, area, hints] = Layout::new(Direction::Vertical, [ Constraint::Length(1), Constraint::default(), Constraint::Length(1) ])
+            .areas(area);
+        self.draw_next_stop(frame, next_stop);
+        self.draw_hints(frame, hints);
+
+        self.panel_areas = if compact {
+            vec![(self.selection, area)]
+        } else {
+            self.layout(area)
+        };
+        for (panel, area) in self.panel_areas.clone() {
+            self.draw_panel(frame, panel, area);
+        }
+    }
+
+    fn layout(&self, area: Rect) -> Vec<(PanelSelection, Rect)> {
+        let layout = Layout::new(Direction::Vertical, [ Constraint::Length(7), Constraint::Length(15), Constraint::default() ])
+            .split(area);
 
-        let layout_1 = Layout::new(Direction::Horizontal, [ Constraint::Min(50), Constraint::default() ])
+        let layout_1 = Layout::new(Direction::Horizontal, [ Constraint::Min(78), Constraint::default() ])
             .split(layout[1]);
 
-        self.draw_basic_info(frame, layout[0]);
-        self.draw_status(frame, layout_1[0]);
-        self.draw_speed_graph(frame, layout_1[1]);
-        self.draw_trip(frame, layout[2]);
+        let layout_2 = Layout::new(Direction::Horizontal, [ Constraint::Percentage(60), Constraint::Percentage(40) ])
+            .split(layout[2]);
+
+        vec![
+            (PanelSelection::BasicInformation, layout[0]),
+            (PanelSelection::StatusInformation, layout_1[0]),
+            (PanelSelection::SpeedInformation, layout_1[1]),
+            (PanelSelection::TripInformation, layout_2[0]),
+            (PanelSelection::MapInformation, layout_2[1]),
+        ]
+    }
+
+    fn draw_panel(&mut self, frame: &mut Frame, panel: PanelSelection, area: Rect) {
+        match panel {
+            PanelSelection::BasicInformation => self.draw_basic_info(frame, area),
+            PanelSelection::StatusInformation => self.draw_status(frame, area),
+            PanelSelection::SpeedInformation => {
+                // delay trend below the speed history
+                let [speed, delay] = Layout::new(Direction::Vertical, [ Constraint::default(), Constraint::Length(6) ])
+                    .areas(area);
+                if self.distance_graph {
+                    self.draw_distance_graph(frame, speed);
+                } else {
+                    self.draw_speed_graph(frame, speed);
+                }
+                self.draw_delay_graph(frame, delay);
+            }
+            PanelSelection::TripInformation => self.draw_trip(frame, area),
+            PanelSelection::MapInformation => self.draw_map(frame, area),
+        }
+    }
+
+    fn panel_at(&self, column: u16, row: u16) -> Option<PanelSelection> {
+        self.panel_areas.iter()
+            .find(|(_, area)| area.contains((column, row).into()))
+            .map(|(panel, _)| *panel)
+    }
+
+    fn notify(&mut self, notice: String) {
+        self.notice = Some((notice, Instant::now()));
+    }
+
+    fn log(&mut self, message: &str) {
+        let Some(path) = &self.log else { return };
+        if let Err(e) = export::append_log(path, message) {
+            self.notify(format!("{} {}", self.labels.log_failed, e));
+        }
+    }
+
+    // what the buffered history adds up to, printed after leaving the UI
+    pub fn recap(&self) -> Option<String> {
+        let recap = stats::recap(&self.data)?;
+        let last = self.data.back()?;
+        let l = self.labels;
+
+        let rows = aligned(&[
+            (l.recap_distance, units::fmt_distance(recap.distance, self.units, l.numbers)),
+            (l.recap_max_speed, units::fmt_speed(recap.max_speed, self.units, l.numbers)),
+            (l.recap_average_speed, units::fmt_speed(recap.average_speed, self.units, l.numbers)),
+            (l.recap_stops, recap.stops.to_string()),
+            (l.recap_delay, recap.delay.map_or_else(|| String::from(l.unknown), |delay| format!("{:+} min", delay))),
+        ]);
+        Some(format!("{} {} {}\n{}", l.recap_title, last.trip.trip.trainType, last.trip.trip.vzn, rows))
+    }
+
+    // write route and driven track as GPX into the working directory
+    fn export_gpx(&mut self) {
+        let Some(info) = self.data.back() else { return };
+
+        let trip = &info.trip.trip;
+        let path = format!("{}_{}{}.gpx", trip.tripDate, trip.trainType, trip.vzn);
+
+        match fs::write(&path, export::to_gpx(&self.data)) {
+            Ok(()) => self.notify(format!("{} {}", self.labels.exported_to, path)),
+            Err(e) => self.notify(format!("{} {}", self.labels.export_failed, e)),
+        }
+    }
+
+    // move the station selection over the listed stops, staying within the route or going round at the ends
+    fn select_station(&mut self, offset: isize) {
+        let Some(info) = self.current() else { return };
+        let listed = listed(&info.trip.trip.stops, self.hide_passed);
+        let count = listed.len();
+        let last = count.saturating_sub(1);
+        let row = listed.iter().position(|&i| i == self.selected_station).unwrap_or(0);
+        let row = match row.checked_add_signed(offset) {
+            Some(row) if row <= last => row,
+            _ if self.wrap_stations && count > 0 => (row as isize + offset).rem_euclid(count as isize) as usize,
+            Some(_) => last,
+            None => 0,
+        };
+        self.selected_station = listed.get(row).copied().unwrap_or(0);
+    }
+
+    fn select_end(&mut self, last: bool) {
+        let Some(info) = self.current() else { return };
+        let listed = listed(&info.trip.trip.stops, self.hide_passed);
+        let end = if last { listed.last() } else { listed.first() };
+        self.selected_station = end.copied().unwrap_or(0);
+    }
+
+    // typing into the station search, selects the first match as you type
+    fn search_input(&mut self, key: KeyCode) {
+        let Some((query, previous)) = &mut self.search else { return };
+
+        match key {
+            KeyCode::Esc => {
+                self.selected_station = *previous;
+                self.search = None;
+                return;
+            }
+            KeyCode::Enter => {
+                self.search = None;
+                return;
+            }
+            KeyCode::Backspace => { query.pop(); }
+            KeyCode::Char(c) => query.push(c),
+            _ => return,
+        }
+
+        let Some(info) = shown(&self.data, self.scrub) else { return };
+        let needle = query.to_lowercase();
+        let found = if needle.is_empty() {
+            Some(*previous)
+        } else {
+            let stops = &info.trip.trip.stops;
+            listed(stops, self.hide_passed).into_iter().find(|&i| stops[i].station.name.to_lowercase().contains(&needle))
+        };
+
+        if let Some(found) = found {
+            self.selected_station = found;
+        }
+    }
+
+    // the raw data view scrolls and swallows everything else, closing it must not quit
+    fn debug_input(&mut self, code: KeyCode) {
+        let Some(offset) = &mut self.debug else { return };
+        let action = self.keymap.action(code);
+
+        match code {
+            KeyCode::Esc => { self.debug = None; }
+            _ if matches!(action, Some(Action::Debug | Action::Quit)) => { self.debug = None; }
+            KeyCode::PageDown => { *offset = offset.saturating_add(10); }
+            KeyCode::PageUp => { *offset = offset.saturating_sub(10); }
+            KeyCode::Home => { *offset = 0; }
+            _ if action == Some(Action::StationDown) => { *offset = offset.saturating_add(1); }
+            _ if action == Some(Action::StationUp) => { *offset = offset.saturating_sub(1); }
+            _ => (),
+        }
     }
 
     // update state (query API, move graphs, ...)
-    fn tick(&mut self) {
-        let files = ApiPaths {
-            status: PathBuf::from("sample/status.json"),
-            trip: PathBuf::from("sample/trip.json"),
+    // starts a query unless the last one is still on its way
+    fn fetch(&mut self, tick_rate: Duration) {
+        if self.fetching {
+            return;
+        }
+        // the trip comes along every trip_interval, the status every time
+        let cached_trip = self.data.back()
+            .filter(|_| self.trip_update.is_some_and(|when| when.elapsed() < self.trip_interval))
+            .map(|info| info.trip.clone());
+        self.fetching = self.queries.request(cached_trip, tick_rate);
+    }
+
+    // everything, not just the status
+    fn refresh(&mut self, tick_rate: Duration) {
+        self.trip_update = None;
+        self.fetch(tick_rate);
+        self.refresh_inflight = self.fetching;
+    }
+
+    fn receive(&mut self, response: Response) {
+        self.fetching = false;
+        let Response { result, retries, fetched_trip } = response;
+        for retry in retries {
+            self.log(&retry);
+        }
+
+        // keep showing the previous data on failure
+        let mut info = match result {
+            Ok(info) => info,
+            Err(ApiError::NotActive) => {
+                self.log("no active trip");
+                self.error = None;
+                self.not_active = true;
+                return;
+            }
+            Err(e) => {
+                self.log(&format!("update failed: {}", e));
+                self.error = Some(e.to_string());
+                return;
+            }
         };
 
-        let info = Info::from_file(&files).unwrap();
+        // the portal's speed sometimes sticks at 0 while the position keeps changing
+        if info.status.speed <= 0.0 {
+            if let Some(kmh) = self.data.back().and_then(|previous| info.status.gps_speed(&previous.status)) {
+                info.status.speed = kmh;
+                info.speed_estimated = true;
+            }
+        }
+
+        // a reused trip still has the position it was fetched with, carry it on between fetches so
+        // the history keeps moving; the next fetched trip snaps back to the portal's value
+        if !fetched_trip {
+            if let Some(previous) = self.data.back() {
+                let seconds = info.status.serverTime.saturating_sub(previous.status.serverTime) as f64 / 1000.0;
+                info.trip.trip.actualPosition = stats::extrapolated_position(previous, seconds);
+            }
+        }
+
+        self.log(&format!("updated: {} {}, {:.0} km/h{} at {} m", info.trip.trip.trainType, info.trip.trip.vzn,
+            info.status.speed, if info.speed_estimated { " (gps)" } else { "" }, info.trip.trip.actualPosition));
+
+        self.error = None;
+        self.not_active = false;
+        self.last_update = Instant::now();
+        if fetched_trip {
+            self.trip_update = Some(self.last_update);
+        }
+
+        if let Some(path) = &self.csv_log {
+            if let Err(e) = export::append_csv(path, &info) {
+                self.notify(format!("{} {}", self.labels.csv_failed, e));
+            }
+        }
 
-        if self.data.len() == self.data.capacity() {
+        // an estimate is no record; the record goes up with every faster update, but only beating
+        // the one from before this session is news, and only the first time
+        if let Some((records, _)) = self.records.as_mut().filter(|_| !info.speed_estimated) {
+            if records.update(&info) {
+                self.records_changed = true;
+                if self.record_to_beat.is_some_and(|speed| info.status.speed > speed) {
+                    self.record_to_beat = None;
+                    self.notify(format!("{} {}", self.labels.new_record, units::fmt_speed(info.status.speed, self.units, self.labels.numbers)));
+                }
+            }
+        }
+
+        // the new trip may have fewer stops (other train, shortened route)
+        let last = info.trip.trip.stops.len().saturating_sub(1);
+        self.selected_station = self.selected_station.min(last);
+        if let Some((_, previous)) = &mut self.search {
+            *previous = (*previous).min(last);
+        }
+
+        if let Some(latest) = &self.latest {
+            *latest.lock().expect("Poisoned") = Some(info.clone());
+        }
+
+        // capacity() may be larger than asked for, scrubbing stays on the same entry
+        while self.data.len() >= self.history {
             self.data.pop_front();
+            if let Some(index) = &mut self.scrub {
+                *index = index.saturating_sub(1);
+            }
+        }
+
+        // still on the same train after a restart
+        if let Some(saved) = self.restore.take().filter(|saved| self.data.is_empty() && history::continues(saved, &info)) {
+            self.log(&format!("restored {} saved updates", saved.len()));
+            self.data = saved;
+            while self.data.len() >= self.history {
+                self.data.pop_front();
+            }
         }
 
+        // only when getting there, not for every update at the final stop
+        let arriving = stats::arrived(&info) && self.data.back().is_some_and(|previous| !stats::arrived(previous));
         self.data.push_back(info);
+        if arriving {
+            self.log("arrived");
+            if self.on_arrival.export() {
+                self.export_gpx();
+            }
+            if self.on_arrival.summary() {
+                self.arrival_summary = self.recap();
+            }
+        }
+        self.check_approach();
+        self.check_delay();
+
+        if self.state_saved.elapsed() >= STATE_SAVE_INTERVAL {
+            self.save_state();
+        }
+    }
+
+    // goes off once the train is within approach_alert of the selected stop, once per stop
+    fn check_approach(&mut self) {
+        let Some(range) = self.approach_alert else { return };
+        let Some(trip) = self.data.back().map(|info| &info.trip.trip) else { return };
+        let Some(stop) = trip.stops.get(self.selected_station).filter(|stop| !stop.info.passed) else { return };
+        if stop.distance_from(trip.actualPosition) > range || self.approach_alerted.as_ref() == Some(&stop.station.evaNr) {
+            return;
+        }
+
+        let message = format!("approaching {}", stop.station.name);
+        self.approach_alerted = Some(stop.station.evaNr.clone());
+        self.log(&message);
+        self.bell();
+    }
+
+    // goes off when the final delay grew by delay_alert since it was lowest, which then starts over;
+    // a delay going back and forth doesn't alert more often than DELAY_ALERT_INTERVAL
+    fn check_delay(&mut self) {
+        let Some(threshold) = self.delay_alert else { return };
+        let Some(delay) = self.data.back().and_then(|info| info.trip.trip.final_delay()) else { return };
+        let baseline = self.delay_baseline.map_or(delay, |baseline| baseline.min(delay));
+        self.delay_baseline = Some(baseline);
+
+        let recently = self.delay_alerted.is_some_and(|(_, _, when)| when.elapsed() < DELAY_ALERT_INTERVAL);
+        if delay - baseline < threshold.max(1) || recently {
+            return;
+        }
+
+        self.log(&format!("delay grew from +{} to +{} min", baseline, delay));
+        self.delay_alerted = Some((baseline, delay, Instant::now()));
+        self.delay_baseline = Some(delay);
+        self.bell();
+    }
+
+    fn bell(&self) {
+        if self.alert_bell {
+            // nowhere to show it if the terminal is gone
+            let _ = stdout().write_all(b"\x07").and_then(|()| stdout().flush());
+        }
+    }
+
+    // also after quitting, so nothing since the last interval is lost
+    pub fn save_state(&mut self) {
+        self.state_saved = Instant::now();
+        let mut failures = Vec::new();
+        if let Some((records, path)) = self.records.as_ref().filter(|_| self.records_changed) {
+            match state::save(path, records) {
+                Ok(()) => self.records_changed = false,
+                Err(e) => failures.push(format!("{} {}", self.labels.record_failed, e)),
+            }
+        }
+        if let Some(path) = &self.history_file {
+            if let Err(e) = state::save(path, &self.data) {
+                failures.push(format!("{} {}", self.labels.history_failed, e));
+            }
+        }
+
+        for notice in failures {
+            self.log(&notice);
+            self.notify(notice);
+        }
     }
 
     pub fn enter_loop(&mut self, tick_rate: Duration) -> io::Result<bool> {
         let mut last_tick = Instant::now();
         let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
-        self.tick(); // tick once to initialize
+        self.fetch(tick_rate); // right away, not a tick later
 
         loop {
             terminal.draw(|frame| self.ui(frame))?;
 
-            let timeout = tick_rate.saturating_sub(last_tick.elapsed());
+            // redraw in between queries, keeps the interpolated numbers moving
+            let timeout = tick_rate.saturating_sub(last_tick.elapsed()).min(self.redraw_interval);
 
             if event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind == event::KeyEventKind::Press {
-                        match key.code {
-                            KeyCode::Char('q') => { return Ok(true); }
-                            KeyCode::Tab => { self.selection.next(); }
-                            KeyCode::BackTab => { self.selection.prev(); }
+                let event = event::read()?;
+                if let Event::Key(key) = event {
+                    // raw mode swallows SIGINT, so Ctrl-C arrives here and quits without asking
+                    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                        return Ok(true);
+                    }
+
+                    if key.kind == event::KeyEventKind::Press && self.confirming_quit {
+                        // anything but y keeps running
+                        if key.code == KeyCode::Char('y') {
+                            return Ok(true);
+                        }
+                        self.confirming_quit = false;
+                    } else if key.kind == event::KeyEventKind::Press && self.search.is_some() {
+                        self.search_input(key.code);
+                    } else if key.kind == event::KeyEventKind::Press && self.debug.is_some() {
+                        self.debug_input(key.code);
+                    } else if key.kind == event::KeyEventKind::Press && self.arrival_summary.is_some() {
+                        // any key, it has been read (or wasn't wanted)
+                        self.arrival_summary = None;
+                    } else if key.kind == event::KeyEventKind::Press && self.show_help {
+                        // help swallows all keys, closing it must not quit
+                        let action = self.keymap.action(key.code);
+                        if key.code == KeyCode::Esc || matches!(action, Some(Action::Help | Action::Quit)) {
+                            self.show_help = false;
+                        }
+                    } else if key.kind == event::KeyEventKind::Press {
+                        match self.keymap.action(key.code) {
+                            Some(Action::Quit) if self.confirm_quit => { self.confirming_quit = true; }
+                            Some(Action::Quit) => { return Ok(true); }
+                            Some(Action::NextPanel) => { self.selection.next(); }
+                            Some(Action::PrevPanel) => { self.selection.prev(); }
+                            Some(Action::ExportGpx) => { self.export_gpx(); }
+                            Some(Action::CopySummary) => { self.copy_summary(); }
+                            Some(Action::Help) => { self.show_help = true; }
+                            Some(Action::Debug) => { self.debug = Some(0); }
+                            Some(Action::ToggleSparkline) => { self.sparkline = !self.sparkline; }
+                            Some(Action::ToggleGraph) => { self.distance_graph = !self.distance_graph; }
+                            Some(Action::ToggleUnits) => { self.units = self.units.toggle(); }
+                            // leaving snaps back to live
+                            Some(Action::Scrub) => {
+                                self.scrub = match self.scrub {
+                                    Some(_) => None,
+                                    None => self.data.len().checked_sub(1),
+                                };
+                            }
+                            Some(Action::ScrubBack) if self.scrub.is_some() => { self.scrub = self.scrub.map(|index| index.saturating_sub(1)); }
+                            Some(Action::ScrubForward) if self.scrub.is_some() => {
+                                let last = self.data.len().saturating_sub(1);
+                                self.scrub = self.scrub.map(|index| (index + 1).min(last));
+                            }
+                            Some(Action::Pause) => {
+                                self.paused = !self.paused;
+                                // catch up right away after resuming
+                                if !self.paused {
+                                    last_tick = Instant::now();
+                                    self.fetch(tick_rate);
+                                }
+                            }
+                            // also works while paused; a query on its way is fetching the old trip, so wait for it
+                            Some(Action::Refresh) if self.fetching => { self.refresh_pending = true; }
+                            Some(Action::Refresh) => {
+                                last_tick = Instant::now();
+                                self.refresh(tick_rate);
+                            }
+                            Some(Action::Search) if self.selection == PanelSelection::TripInformation => {
+                                self.search = Some((String::new(), self.selected_station));
+                            }
+                            Some(Action::ToggleDetail) if self.selection == PanelSelection::TripInformation => {
+                                self.selected_station_detailed = !self.selected_station_detailed;
+                            }
+                            Some(Action::StationDown) if self.selection == PanelSelection::TripInformation => { self.select_station(1); }
+                            Some(Action::StationUp) if self.selection == PanelSelection::TripInformation => { self.select_station(-1); }
+                            Some(Action::FirstStation) if self.selection == PanelSelection::TripInformation => { self.select_end(false); }
+                            Some(Action::LastStation) if self.selection == PanelSelection::TripInformation => { self.select_end(true); }
+                            Some(Action::HidePassed) => { self.hide_passed = !self.hide_passed; }
+                            Some(Action::ConnectionStops) => { self.connection_stops = !self.connection_stops; }
                             _ => (),
                         }
                     }
+                } else if let Event::Mouse(mouse) = event {
+                    // overlays and the search keep the focus where it is
+                    let modal = self.confirming_quit || self.show_help || self.arrival_summary.is_some() || self.search.is_some() || self.debug.is_some();
+                    match (mouse.kind, self.panel_at(mouse.column, mouse.row)) {
+                        (MouseEventKind::ScrollDown, _) if self.debug.is_some() => { self.debug_input(KeyCode::Down); }
+                        (MouseEventKind::ScrollUp, _) if self.debug.is_some() => { self.debug_input(KeyCode::Up); }
+                        (MouseEventKind::Down(_), Some(panel)) if !modal => { self.selection = panel; }
+                        (MouseEventKind::ScrollDown, Some(PanelSelection::TripInformation)) if !modal => { self.select_station(1); }
+                        (MouseEventKind::ScrollUp, Some(PanelSelection::TripInformation)) if !modal => { self.select_station(-1); }
+                        _ => (),
+                    }
                 }
             }
 
-            if last_tick.elapsed() >= tick_rate {
+            // shown with the next frame, at most a redraw_interval later; pausing freezes what is
+            // shown, so a tick sent before is dropped (resuming fetches anew), a refresh is not
+            while let Some(response) = self.queries.try_recv() {
+                let refresh = mem::take(&mut self.refresh_inflight);
+                if self.paused && !refresh {
+                    self.fetching = false;
+                } else {
+                    self.receive(response);
+                }
+            }
+            if self.refresh_pending && !self.fetching {
+                self.refresh_pending = false;
+                last_tick = Instant::now();
+                self.refresh(tick_rate);
+            }
+
+            if !self.paused && last_tick.elapsed() >= tick_rate {
                 last_tick = Instant::now();
-                self.tick();
+                self.fetch(tick_rate);
             }
         }
     }
@@ -0,0 +1,23 @@
+// The buffered updates kept in ~/.local/state/bahn-status/history.json, so a restart
+// on the same train picks up where it left off
+
+use std::{collections::VecDeque, path::PathBuf, time::Duration};
+
+use chrono::Utc;
+
+use crate::{api::Info, state};
+
+// older than this, the graphs would mostly show a gap anyway
+const RESTORE_WITHIN: Duration = Duration::from_secs(10 * 60);
+
+pub fn path() -> Option<PathBuf> {
+    state::path("history.json")
+}
+
+// the saved updates are worth continuing with: same train and trip, and not too old
+pub fn continues(saved: &VecDeque<Info>, info: &Info) -> bool {
+    let Some(newest) = saved.back() else { return false };
+    let (before, now) = (&newest.trip.trip, &info.trip.trip);
+    let age = (Utc::now().timestamp_millis() as u64).saturating_sub(newest.status.serverTime);
+    before.vzn == now.vzn && before.tripDate == now.tripDate && age < RESTORE_WITHIN.as_millis() as u64
+}
@@ -0,0 +1,174 @@
+// Mapping of keys to what they do, configurable in the [keys] table
+
+use std::collections::HashMap;
+
+use ratatui::crossterm::event::KeyCode;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    NextPanel,
+    PrevPanel,
+    StationUp,
+    StationDown,
+    FirstStation,
+    LastStation,
+    HidePassed,
+    ConnectionStops,
+    ToggleDetail,
+    ExportGpx,
+    Help,
+    Pause,
+    Refresh,
+    Search,
+    ToggleSparkline,
+    ToggleGraph,
+    ToggleUnits,
+    Scrub,
+    ScrubBack,
+    ScrubForward,
+    Debug,
+    CopySummary,
+}
+
+impl Action {
+    pub const ALL: [Action; 23] = [
+        Action::Quit,
+        Action::NextPanel,
+        Action::PrevPanel,
+        Action::StationUp,
+        Action::StationDown,
+        Action::FirstStation,
+        Action::LastStation,
+        Action::HidePassed,
+        Action::ConnectionStops,
+        Action::ToggleDetail,
+        Action::ExportGpx,
+        Action::Help,
+        Action::Pause,
+        Action::Refresh,
+        Action::Search,
+        Action::ToggleSparkline,
+        Action::ToggleGraph,
+        Action::ToggleUnits,
+        Action::Scrub,
+        Action::ScrubBack,
+        Action::ScrubForward,
+        Action::Debug,
+        Action::CopySummary,
+    ];
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "HashMap<Action, Vec<String>>")]
+pub struct Keymap {
+    bindings: Vec<(KeyCode, Action)>, // first match wins
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap {
+            bindings: vec![
+                (KeyCode::Char('q'), Action::Quit),
+                (KeyCode::Tab, Action::NextPanel),
+                (KeyCode::BackTab, Action::PrevPanel),
+                (KeyCode::Char('k'), Action::StationUp),
+                (KeyCode::Up, Action::StationUp),
+                (KeyCode::Char('j'), Action::StationDown),
+                (KeyCode::Down, Action::StationDown),
+                (KeyCode::Char('g'), Action::FirstStation),
+                (KeyCode::Home, Action::FirstStation),
+                (KeyCode::Char('G'), Action::LastStation),
+                (KeyCode::End, Action::LastStation),
+                (KeyCode::Char('h'), Action::HidePassed),
+                (KeyCode::Char('c'), Action::ConnectionStops),
+                (KeyCode::Enter, Action::ToggleDetail),
+                (KeyCode::Char('x'), Action::ExportGpx),
+                (KeyCode::Char('?'), Action::Help),
+                (KeyCode::Char(' '), Action::Pause),
+                (KeyCode::Char('r'), Action::Refresh),
+                (KeyCode::Char('/'), Action::Search),
+                (KeyCode::Char('s'), Action::ToggleSparkline),
+                (KeyCode::Char('d'), Action::ToggleGraph),
+                (KeyCode::Char('u'), Action::ToggleUnits),
+                (KeyCode::Char('t'), Action::Scrub),
+                (KeyCode::Left, Action::ScrubBack),
+                (KeyCode::Right, Action::ScrubForward),
+                (KeyCode::F(12), Action::Debug),
+                (KeyCode::Char('y'), Action::CopySummary),
+            ],
+        }
+    }
+}
+
+// configured actions replace their default keys, the others stay as they are
+impl TryFrom<HashMap<Action, Vec<String>>> for Keymap {
+    type Error = String;
+
+    fn try_from(configured: HashMap<Action, Vec<String>>) -> Result<Keymap, String> {
+        let mut bindings = Vec::new();
+        for (action, keys) in &configured {
+            for key in keys {
+                bindings.push((parse_key(key)?, *action));
+            }
+        }
+
+        let defaults = Keymap::default().bindings;
+        bindings.extend(defaults.into_iter().filter(|(_, action)| !configured.contains_key(action)));
+
+        Ok(Keymap { bindings })
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.bindings.iter().find(|(bound, _)| *bound == key).map(|(_, action)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<KeyCode> {
+        self.bindings.iter().filter(|(_, bound)| *bound == action).map(|(key, _)| *key).collect()
+    }
+}
+
+// inverse of parse_key
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => String::from("Space"),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+// single characters or the usual key names ("Tab", "Up", "F5", ...)
+fn parse_key(key: &str) -> Result<KeyCode, String> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+
+    let code = match key {
+        "Space" => KeyCode::Char(' '),
+        "Tab" => KeyCode::Tab,
+        "BackTab" => KeyCode::BackTab,
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Backspace" => KeyCode::Backspace,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ => match key.strip_prefix('F').and_then(|n| n.parse().ok()) {
+            Some(n) => KeyCode::F(n),
+            None => return Err(format!("Unknown key: {}", key)),
+        },
+    };
+
+    Ok(code)
+}
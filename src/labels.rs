@@ -0,0 +1,506 @@
+// User-facing strings per language
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::{keymap::Action, units::NumberFormat};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "en")]
+    English,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Language, String> {
+        match s {
+            "de" => Ok(Language::German),
+            "en" => Ok(Language::English),
+            _ => Err(format!("Unknown language: {} (expected de or en)", s)),
+        }
+    }
+}
+
+impl Labels {
+    pub fn action(&self, action: Action) -> &'static str {
+        match action {
+            Action::Quit => self.action_quit,
+            Action::NextPanel => self.action_next_panel,
+            Action::PrevPanel => self.action_prev_panel,
+            Action::StationUp => self.action_station_up,
+            Action::StationDown => self.action_station_down,
+            Action::FirstStation => self.action_first_station,
+            Action::LastStation => self.action_last_station,
+            Action::HidePassed => self.action_hide_passed,
+            Action::ConnectionStops => self.action_connection_stops,
+            Action::ToggleDetail => self.action_toggle_detail,
+            Action::ExportGpx => self.action_export_gpx,
+            Action::Help => self.action_help,
+            Action::Pause => self.action_pause,
+            Action::Refresh => self.action_refresh,
+            Action::Search => self.action_search,
+            Action::ToggleSparkline => self.action_toggle_sparkline,
+            Action::ToggleGraph => self.action_toggle_graph,
+            Action::ToggleUnits => self.action_toggle_units,
+            Action::Scrub => self.action_scrub,
+            Action::ScrubBack => self.action_scrub_back,
+            Action::ScrubForward => self.action_scrub_forward,
+            Action::Debug => self.action_debug,
+            Action::CopySummary => self.action_copy_summary,
+        }
+    }
+}
+
+impl Language {
+    pub fn labels(self) -> &'static Labels {
+        match self {
+            Language::German => &GERMAN,
+            Language::English => &ENGLISH,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Labels {
+    pub numbers: NumberFormat,
+
+    // basic information
+    pub basic_title: &'static str,
+    pub train_type: &'static str,
+    pub train_id: &'static str,
+    pub wagon_class: &'static str,
+    pub service_level: &'static str,
+    pub service_available: &'static str,
+    pub service_partial: &'static str,
+    pub service_none: &'static str,
+    pub bap_installed: &'static str,
+    pub route: &'static str,
+    pub from: &'static str,
+    pub to: &'static str,
+
+    // status
+    pub status_title: &'static str,
+    pub speed: &'static str,
+    pub average_speed: &'static str,
+    pub average_moving: &'static str,
+    pub speed_estimated: &'static str,
+    pub internet: &'static str,
+    pub connectivity: &'static str,
+    pub total_distance: &'static str,
+    pub traveled: &'static str,
+    pub remaining: &'static str,
+    pub next_stop_distance: &'static str,
+    pub next_stop_countdown: &'static str,
+    pub position: &'static str,
+    pub gps: &'static str,
+    pub server_connection: &'static str,
+    pub arrival_final: &'static str,
+    pub next_stop: &'static str,
+    pub next_stop_in: &'static str,
+    pub next_stop_at: &'static str,
+    pub unknown: &'static str,
+    pub compass: [&'static str; 8], // clockwise from north
+    pub arrival_planned: &'static str,
+    pub arrival_estimated: &'static str,
+    pub left: &'static str,
+
+    // speed graph
+    pub speed_title: &'static str,
+    pub speed_current: &'static str,
+    pub delay_title: &'static str,
+    pub distance_title: &'static str,
+
+    // trip
+    pub trip_title: &'static str,
+    pub last_update: &'static str,
+    pub paused: &'static str,
+    pub scrubbing: &'static str,
+    pub seconds_ago_prefix: &'static str,
+    pub seconds_ago_suffix: &'static str,
+    pub arrival: &'static str,
+    pub departure: &'static str,
+    pub arrival_delay: &'static str,
+    pub departure_delay: &'static str,
+    pub track: &'static str,
+    pub segment: &'static str, // distance from the previous stop
+    pub since: &'static str,
+    pub instead_of: &'static str,
+    pub delay_reasons: &'static str,
+    pub no_delay_reasons: &'static str,
+    pub no_trip: &'static str,
+    pub search_prompt: &'static str,
+    pub connection_title: &'static str,
+    pub connection_train: &'static str,
+    pub connection_departure: &'static str,
+    pub connection_conflict: &'static str,
+    pub connection_stops_title: &'static str,
+
+    // map
+    pub map_title: &'static str,
+
+    // help
+    pub help_title: &'static str,
+    pub debug_title: &'static str,
+    pub help_keys: &'static str,
+    pub help_panels: &'static str,
+    pub help_basic: &'static str,
+    pub help_status: &'static str,
+    pub help_speed: &'static str,
+    pub help_trip: &'static str,
+    pub help_map: &'static str,
+    pub action_quit: &'static str,
+    pub action_next_panel: &'static str,
+    pub action_prev_panel: &'static str,
+    pub action_station_up: &'static str,
+    pub action_station_down: &'static str,
+    pub action_first_station: &'static str,
+    pub action_last_station: &'static str,
+    pub action_hide_passed: &'static str,
+    pub action_connection_stops: &'static str,
+    pub action_toggle_detail: &'static str,
+    pub action_export_gpx: &'static str,
+    pub action_help: &'static str,
+    pub action_pause: &'static str,
+    pub action_refresh: &'static str,
+    pub action_search: &'static str,
+    pub action_toggle_sparkline: &'static str,
+    pub action_toggle_graph: &'static str,
+    pub action_toggle_units: &'static str,
+    pub action_scrub: &'static str,
+    pub action_scrub_back: &'static str,
+    pub action_scrub_forward: &'static str,
+    pub action_debug: &'static str,
+    pub action_copy_summary: &'static str,
+
+    // status bar
+    pub hint_panel: &'static str,
+    pub hint_station: &'static str,
+    pub hint_details: &'static str,
+    pub hint_search: &'static str,
+    pub hint_hide_passed: &'static str,
+    pub hint_sparkline: &'static str,
+    pub hint_graph: &'static str,
+    pub hint_units: &'static str,
+    pub hint_scrub: &'static str,
+    pub hint_help: &'static str,
+    pub hint_quit: &'static str,
+
+    // banners and notices
+    pub waiting: &'static str,
+    pub update_failed: &'static str,
+    pub stale_data: &'static str,
+    pub approaching: &'static str,
+    pub delay_grown: &'static str,
+    pub exported_to: &'static str,
+    pub copied: &'static str,
+    pub copy_failed: &'static str,
+    pub export_failed: &'static str,
+    pub csv_failed: &'static str,
+    pub log_failed: &'static str,
+    pub new_record: &'static str,
+    pub record_failed: &'static str,
+    pub history_failed: &'static str,
+    pub quit_title: &'static str,
+    pub quit_question: &'static str,
+
+    // summary after quitting
+    pub recap_title: &'static str,
+    pub arrived_title: &'static str,
+    pub recap_distance: &'static str,
+    pub recap_max_speed: &'static str,
+    pub recap_average_speed: &'static str,
+    pub recap_stops: &'static str,
+    pub recap_delay: &'static str,
+}
+
+const GERMAN: Labels = Labels {
+    numbers: NumberFormat { thousands: '.', decimal: ',' },
+
+    basic_title: "Grundlegende Informationen",
+    train_type: "Schienenfahrzeugtyp:",
+    train_id: "Schienenfahrzeugbezeichnung:",
+    wagon_class: "Sozioökonomisches Milieu:",
+    service_level: "Bordservice:",
+    service_available: "verfügbar",
+    service_partial: "eingeschränkt",
+    service_none: "nicht verfügbar",
+    bap_installed: "Bord-API verfügbar",
+    route: "Streckenführung:",
+    from: "von",
+    to: "nach",
+
+    status_title: "Statusinformation",
+    speed: "Aktuelle Geschwindigkeit:",
+    average_speed: "   Gleitender Mittelwert:",
+    average_moving: "ohne Halte",
+    speed_estimated: "aus GPS geschätzt",
+    internet: "Internetzwerkverbindungsgüte:",
+    connectivity: "Verbindung:",
+    total_distance: "Gesamte Streckenlänge:",
+    traveled: "Davon bereits zurückgelegt:",
+    remaining: "Verbleibend (nach Adam Riese):",
+    next_stop_distance: "Entfernung zum nächsten Halt:",
+    next_stop_countdown: "Ankunft am nächsten Halt in:",
+    position: "Aktuelle geographische Lage:",
+    gps: "GPS-Empfang:",
+    server_connection: "Serververbindung",
+    arrival_final: "Ankunft:",
+    next_stop: "Nächster Halt:",
+    next_stop_in: "in",
+    next_stop_at: "an",
+    unknown: "unbekannt",
+    compass: ["N", "NO", "O", "SO", "S", "SW", "W", "NW"],
+    arrival_planned: "planmäßig",
+    arrival_estimated: "berechnet",
+    left: "übrig",
+
+    speed_title: "Geschwindigkeitsverlauf",
+    speed_current: "jetzt",
+    delay_title: "Verspätung am Ziel",
+    distance_title: "Zurückgelegte Strecke",
+
+    trip_title: "Streckenverlauf",
+    last_update: "Zuletzt aktualisiert:",
+    paused: "pausiert",
+    scrubbing: "Rückblick",
+    seconds_ago_prefix: "vor ",
+    seconds_ago_suffix: " Sekunden",
+    arrival: "Ankunft:",
+    departure: "Abfahrt:",
+    arrival_delay: "Verspätung (Ankunft):",
+    departure_delay: "Verspätung (Abfahrt):",
+    track: "Gleis:",
+    segment: "Abschnitt:",
+    since: "seit",
+    instead_of: "statt",
+    delay_reasons: "Verspätungsgründe:",
+    no_delay_reasons: "Keine Verspätungsgründe",
+    no_trip: "Keine aktive Fahrt gefunden",
+    search_prompt: "Suche:",
+    connection_title: "Anschluss",
+    connection_train: "Zug:",
+    connection_departure: "Abfahrt:",
+    connection_conflict: "Anschluss wird voraussichtlich nicht erreicht!",
+    connection_stops_title: "Weiterfahrt",
+
+    map_title: "Karte",
+
+    help_title: "Hilfe",
+    debug_title: "Rohdaten",
+    help_keys: "Tastenbelegung",
+    help_panels: "Panels",
+    help_basic: "Zug und Strecke",
+    help_status: "Geschwindigkeit, Verbindung und Entfernungen",
+    help_speed: "Verlauf der Geschwindigkeit",
+    help_trip: "Halte mit Zeiten und Verspätungen",
+    help_map: "Strecke und aktuelle Position",
+    action_quit: "Beenden",
+    action_next_panel: "Nächstes Panel",
+    action_prev_panel: "Vorheriges Panel",
+    action_station_up: "Vorherige Station",
+    action_station_down: "Nächste Station",
+    action_first_station: "Erste Station",
+    action_last_station: "Letzte Station",
+    action_hide_passed: "Passierte Halte aus-/einblenden",
+    action_connection_stops: "Halte des Anschlusszugs ein-/ausblenden",
+    action_toggle_detail: "Stationsdetails ein/aus",
+    action_export_gpx: "Als GPX exportieren",
+    action_help: "Hilfe ein/aus",
+    action_pause: "Aktualisierung anhalten/fortsetzen",
+    action_refresh: "Jetzt aktualisieren",
+    action_search: "Station suchen",
+    action_toggle_sparkline: "Geschwindigkeit als Sparkline/Graph",
+    action_toggle_graph: "Geschwindigkeit/Strecke",
+    action_toggle_units: "Metrisch/imperial",
+    action_scrub: "Rückblick ein/aus",
+    action_scrub_back: "Rückblick: früher",
+    action_scrub_forward: "Rückblick: später",
+    action_debug: "Rohdaten anzeigen",
+    action_copy_summary: "Zusammenfassung kopieren",
+
+    hint_panel: "Panel wechseln",
+    hint_station: "Station",
+    hint_details: "Details",
+    hint_search: "Suchen",
+    hint_hide_passed: "Passierte",
+    hint_sparkline: "Sparkline",
+    hint_graph: "Strecke",
+    hint_units: "Einheiten",
+    hint_scrub: "Rückblick",
+    hint_help: "Hilfe",
+    hint_quit: "Beenden",
+
+    waiting: "Warte auf Daten...",
+    update_failed: "Aktualisierung fehlgeschlagen:",
+    stale_data: "Daten veraltet — Verbindung prüfen",
+    approaching: "Gleich Ausstieg:",
+    delay_grown: "Verspätung am Ziel gestiegen:",
+    exported_to: "Exportiert nach",
+    copied: "In die Zwischenablage kopiert:",
+    copy_failed: "Kopieren fehlgeschlagen:",
+    export_failed: "Export fehlgeschlagen:",
+    csv_failed: "CSV-Protokoll fehlgeschlagen:",
+    log_failed: "Protokoll fehlgeschlagen:",
+    new_record: "Neuer Rekord!",
+    record_failed: "Rekord nicht gespeichert:",
+    history_failed: "Verlauf nicht gespeichert:",
+    quit_title: "Beenden",
+    quit_question: "Wirklich beenden? [y/n]",
+
+    recap_title: "Zusammenfassung",
+    arrived_title: "Angekommen",
+    recap_distance: "Gefahren:",
+    recap_max_speed: "Höchstgeschwindigkeit:",
+    recap_average_speed: "Durchschnitt:",
+    recap_stops: "Passierte Halte:",
+    recap_delay: "Verspätung aufgebaut:",
+};
+
+const ENGLISH: Labels = Labels {
+    numbers: NumberFormat { thousands: ',', decimal: '.' },
+
+    basic_title: "Basic information",
+    train_type: "Train type:",
+    train_id: "Train designation:",
+    wagon_class: "Socio-economic milieu:",
+    service_level: "Onboard service:",
+    service_available: "available",
+    service_partial: "limited",
+    service_none: "not available",
+    bap_installed: "onboard API available",
+    route: "Route:",
+    from: "from",
+    to: "to",
+
+    status_title: "Status",
+    speed: "Current speed:",
+    average_speed: "   Moving average:",
+    average_moving: "without stops",
+    speed_estimated: "estimated from GPS",
+    internet: "Internet connection quality:",
+    connectivity: "Connection:",
+    total_distance: "Total distance:",
+    traveled: "Traveled so far:",
+    remaining: "Remaining (simple math):",
+    next_stop_distance: "Distance to next stop:",
+    next_stop_countdown: "Next stop in:",
+    position: "Current location:",
+    gps: "GPS fix:",
+    server_connection: "server connection",
+    arrival_final: "Arrival:",
+    next_stop: "Next stop:",
+    next_stop_in: "in",
+    next_stop_at: "at",
+    unknown: "unknown",
+    compass: ["N", "NE", "E", "SE", "S", "SW", "W", "NW"],
+    arrival_planned: "timetable",
+    arrival_estimated: "calculated",
+    left: "left",
+
+    speed_title: "Speed history",
+    speed_current: "now",
+    delay_title: "Delay at destination",
+    distance_title: "Distance traveled",
+
+    trip_title: "Route",
+    last_update: "Last update:",
+    paused: "paused",
+    scrubbing: "history",
+    seconds_ago_prefix: "",
+    seconds_ago_suffix: " seconds ago",
+    arrival: "Arrival:",
+    departure: "Departure:",
+    arrival_delay: "Delay (arrival):",
+    departure_delay: "Delay (departure):",
+    track: "Track:",
+    segment: "Section:",
+    since: "from",
+    instead_of: "instead of",
+    delay_reasons: "Delay reasons:",
+    no_delay_reasons: "No delay reasons",
+    no_trip: "No active trip found",
+    search_prompt: "Search:",
+    connection_title: "Connection",
+    connection_train: "Train:",
+    connection_departure: "Departure:",
+    connection_conflict: "Connection will probably be missed!",
+    connection_stops_title: "Onward journey",
+
+    map_title: "Map",
+
+    help_title: "Help",
+    debug_title: "Raw data",
+    help_keys: "Keys",
+    help_panels: "Panels",
+    help_basic: "Train and route",
+    help_status: "Speed, connection and distances",
+    help_speed: "Speed over time",
+    help_trip: "Stops with times and delays",
+    help_map: "Route and current position",
+    action_quit: "Quit",
+    action_next_panel: "Next panel",
+    action_prev_panel: "Previous panel",
+    action_station_up: "Previous station",
+    action_station_down: "Next station",
+    action_first_station: "First station",
+    action_last_station: "Last station",
+    action_hide_passed: "Hide/show passed stops",
+    action_connection_stops: "Show/hide the connecting train's stops",
+    action_toggle_detail: "Toggle station details",
+    action_export_gpx: "Export as GPX",
+    action_help: "Toggle help",
+    action_pause: "Pause/resume updates",
+    action_refresh: "Update now",
+    action_search: "Search station",
+    action_toggle_sparkline: "Speed as sparkline/graph",
+    action_toggle_graph: "Speed/distance",
+    action_toggle_units: "Metric/imperial units",
+    action_scrub: "Toggle history scrubbing",
+    action_scrub_back: "Scrub: earlier",
+    action_scrub_forward: "Scrub: later",
+    action_debug: "Show raw data",
+    action_copy_summary: "Copy summary",
+
+    hint_panel: "Switch panel",
+    hint_station: "Station",
+    hint_details: "Details",
+    hint_search: "Search",
+    hint_hide_passed: "Passed",
+    hint_sparkline: "Sparkline",
+    hint_graph: "Distance",
+    hint_units: "Units",
+    hint_scrub: "Scrub",
+    hint_help: "Help",
+    hint_quit: "Quit",
+
+    waiting: "Waiting for data...",
+    update_failed: "Update failed:",
+    stale_data: "Data is stale — check the connection",
+    approaching: "Your stop is coming up:",
+    delay_grown: "Delay at the destination grew:",
+    exported_to: "Exported to",
+    copied: "Copied to the clipboard:",
+    copy_failed: "Copy failed:",
+    export_failed: "Export failed:",
+    csv_failed: "CSV log failed:",
+    log_failed: "Log failed:",
+    new_record: "New record!",
+    record_failed: "Record not saved:",
+    history_failed: "History not saved:",
+    quit_title: "Quit",
+    quit_question: "Really quit? [y/n]",
+
+    recap_title: "Summary",
+    arrived_title: "Arrived",
+    recap_distance: "Distance:",
+    recap_max_speed: "Top speed:",
+    recap_average_speed: "Average speed:",
+    recap_stops: "Stops passed:",
+    recap_delay: "Delay gained:",
+};
@@ -0,0 +1,19 @@
+// Everything but the command line, so tests/ can get at it
+
+pub mod api;
+pub mod check;
+pub mod config;
+pub mod export;
+pub mod frontend;
+pub mod history;
+pub mod keymap;
+pub mod labels;
+pub mod metrics;
+pub mod query;
+pub mod records;
+pub mod serve;
+pub mod state;
+pub mod stats;
+pub mod text;
+pub mod theme;
+pub mod units;
@@ -1,32 +1,214 @@
-use std::{error::Error, io::stdout, time::Duration};
+use std::{error::Error, io::{self, stdout, Write}, path::PathBuf, process};
 
-use frontend::Frontend;
+use bahn_status::{
+    api::{self, ApiEndpoints, ApiPaths, DataSource, Info},
+    check,
+    config::Config,
+    frontend::Frontend,
+    labels::Language,
+    serve::{self, Latest},
+    theme::{Preset, Theme},
+};
+use clap::Parser;
 use ratatui::crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 
-mod api;
-mod frontend;
+// everything given here wins over the config file
+#[derive(Parser, Debug)]
+#[command(version, about = "TUI for the ICE portal's trip and status information")]
+struct Args {
+    /// Replay the recorded samples instead of asking the train
+    #[arg(long)]
+    demo: bool,
+
+    /// Config file instead of ~/.config/bahn-status/config.toml
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Status endpoint instead of the configured or detected one
+    #[arg(long, value_name = "URL", env = "BAHN_STATUS_URL")]
+    status_url: Option<String>,
+
+    /// Trip endpoint instead of the configured or detected one
+    #[arg(long, value_name = "URL", env = "BAHN_TRIP_URL")]
+    trip_url: Option<String>,
+
+    /// Language of the UI (de or en)
+    #[arg(long, value_name = "LANG")]
+    lang: Option<Language>,
+
+    /// Color theme (default or colorblind), replaces the configured one
+    #[arg(long, value_name = "NAME")]
+    theme: Option<Preset>,
+
+    /// Number of buffered updates
+    #[arg(long, value_name = "N")]
+    history: Option<usize>,
+
+    /// Milliseconds between queries to the portal, at least 100
+    #[arg(long, value_name = "MS", visible_alias = "poll-ms")]
+    tick_ms: Option<u64>,
+
+    /// Milliseconds between redraws, e.g. for smoother countdowns
+    #[arg(long, value_name = "MS")]
+    redraw_ms: Option<u64>,
+
+    /// Append every update to a CSV file
+    #[arg(long, value_name = "PATH")]
+    log_csv: Option<PathBuf>,
+
+    /// Log every update, error and retry to a file
+    #[arg(long, value_name = "PATH")]
+    log: Option<PathBuf>,
+
+    /// Serve the newest data as JSON at http://127.0.0.1:PORT/status
+    #[arg(long, value_name = "PORT")]
+    serve: Option<u16>,
+
+    /// Serve the newest data for Prometheus at http://127.0.0.1:PORT/metrics
+    #[arg(long, value_name = "PORT")]
+    metrics: Option<u16>,
+
+    /// Query once, print and exit without starting the UI
+    #[arg(long, requires = "json")]
+    once: bool,
+
+    /// Print as JSON (with --once)
+    #[arg(long, requires = "once")]
+    json: bool,
+
+    /// Query once and report what the config, endpoints and portal gave, exit 1 if the UI would show nothing
+    #[arg(long, conflicts_with = "once")]
+    check: bool,
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
-    // let endpoints = ApiEndpoints {
-    //     status: String::from("https://iceportal.de/api1/rs/status"),
-    //     trip: String::from("https://iceportal.de/api1/rs/tripInfo/trip"),
-    // };
+    let args = Args::parse();
 
-    // let info = Info::query(&endpoints)?;
+    // for --check, what was actually used
+    let config_file = match args.config.clone().or_else(Config::path) {
+        Some(path) if path.exists() => path.display().to_string(),
+        Some(path) => format!("{} (not found, defaults)", path.display()),
+        None => String::from("none (defaults)"),
+    };
+    let mut config = Config::load(args.config)?;
+    let mut endpoints_from = if args.demo {
+        "recorded samples (--demo)"
+    } else if args.status_url.is_some() || args.trip_url.is_some() {
+        "command line or environment"
+    } else if config.endpoints.is_some() {
+        "config"
+    } else {
+        "first answering portal"
+    };
 
-    let tick_rate = Duration::from_millis(1000); // update every second
+    // either one fixes the endpoints, the other one comes from the config
+    if args.status_url.is_some() || args.trip_url.is_some() {
+        let mut endpoints = config.endpoints.take().unwrap_or_default();
+        if let Some(url) = args.status_url {
+            endpoints.status = url;
+        }
+        if let Some(url) = args.trip_url {
+            endpoints.trip = url;
+        }
+        config.endpoints = Some(endpoints);
+    }
+    if let Some(language) = args.lang {
+        config.language = language;
+    }
+    if let Some(preset) = args.theme {
+        config.theme = Theme::preset(preset);
+    }
+    if let Some(history) = args.history {
+        config.history = history;
+    }
+    if let Some(tick_ms) = args.tick_ms {
+        config.tick_rate_ms = tick_ms;
+    }
+    if let Some(redraw_ms) = args.redraw_ms {
+        config.redraw_ms = redraw_ms;
+    }
+    if args.log_csv.is_some() {
+        config.log_csv = args.log_csv;
+    }
+    if args.log.is_some() {
+        config.log = args.log;
+    }
+    if args.serve.is_some() {
+        config.serve = args.serve;
+    }
+    if args.metrics.is_some() {
+        config.metrics = args.metrics;
+    }
+
+    let client = api::client()?;
+    let source = if args.demo {
+        DataSource::Files(ApiPaths {
+            status: PathBuf::from("sample/status.json"),
+            trip: PathBuf::from("sample/trip.json"),
+        })
+    } else {
+        // before the terminal is taken over, so a slow or missing network doesn't look like a hang
+        let endpoints = config.endpoints.clone().or_else(|| ApiEndpoints::detect(&client, &config.portals,
+            |base| eprintln!("Probing {} ...", base)));
+        DataSource::Api(endpoints.unwrap_or_else(|| {
+            endpoints_from = "first portal, none of them answered";
+            ApiEndpoints::first(&config.portals)
+        }))
+    };
+
+    if args.check {
+        if check::run(&client, &source, &config_file, endpoints_from) {
+            return Ok(());
+        }
+        process::exit(1);
+    }
+
+    // single query for scripts, without ever touching the terminal
+    if args.once {
+        let info = match &source {
+            DataSource::Api(endpoints) => Info::query(&client, endpoints)?,
+            DataSource::Files(paths) => Info::from_file(paths)?,
+        };
+        // `| head` closing the pipe early is fine
+        return match writeln!(stdout(), "{}", serde_json::to_string_pretty(&info)?) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => Ok(result?),
+        };
+    }
+
+    // both servers hand out the same data
+    let latest = (config.serve.is_some() || config.metrics.is_some()).then(Latest::default);
+    if let Some(latest) = &latest {
+        for port in config.serve.iter().chain(&config.metrics) {
+            serve::spawn(*port, latest)?;
+        }
+    }
 
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableMouseCapture)?;
 
-    let mut frontend = Frontend::new(50)?;
-    frontend.enter_loop(tick_rate)?;
+    // restore the terminal even if the loop failed
+    let result = Frontend::new(&config, source, client, latest).and_then(|mut frontend| {
+        frontend.enter_loop(config.tick_rate())?;
+        frontend.save_state();
+        Ok(frontend.recap())
+    });
 
     disable_raw_mode()?;
+    stdout().execute(DisableMouseCapture)?;
     stdout().execute(LeaveAlternateScreen)?;
 
+    // back on the normal screen, so it stays visible
+    if let Some(recap) = result? {
+        if config.exit_summary {
+            println!("{}", recap);
+        }
+    }
+
     Ok(())
 }
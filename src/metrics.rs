@@ -0,0 +1,26 @@
+// Prometheus text format of the newest data, served at /metrics
+
+use std::fmt::{self, Write as _};
+
+use crate::{api::{Info, Signal}, stats};
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl fmt::Display) {
+    // writing into a String can't fail
+    writeln!(out, "# HELP {} {}\n# TYPE {} gauge\n{} {}", name, help, name, name, value).unwrap();
+}
+
+pub fn render(info: &Info) -> String {
+    let trip = &info.trip.trip;
+    let mut out = String::new();
+
+    gauge(&mut out, "train_speed_kmh", "Current speed in km/h.", info.status.speed);
+    if let Some(delay) = trip.final_delay() {
+        gauge(&mut out, "train_delay_minutes", "Expected delay at the final stop in minutes.", delay);
+    }
+    gauge(&mut out, "train_distance_remaining_km", "Distance left to the final stop in km.",
+        stats::remaining(info, trip.actualPosition) as f64 / 1000.0);
+    gauge(&mut out, "train_connectivity", "Internet connectivity, 0 unknown, 1 weak, 2 unstable, 3 middle, 4 high.",
+        Signal::parse(&info.status.connectivity.currentState).level());
+
+    out
+}
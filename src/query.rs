@@ -0,0 +1,66 @@
+// Queries run on their own thread, so a slow portal holds up neither input nor redraws
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use reqwest::blocking::Client;
+
+use crate::api::{ApiError, DataSource, Info, TripInfo};
+
+const RETRY_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+
+struct Request {
+    trip: Option<TripInfo>, // still fresh enough, only the status is fetched
+    budget: Duration, // retries must fit in, or the next update would wait for this one
+}
+
+pub struct Response {
+    pub result: Result<Info, ApiError>,
+    pub retries: Vec<String>, // every failed attempt that was retried
+    pub fetched_trip: bool, // false if the trip was the one passed in
+}
+
+// one query at a time, answered in order
+pub struct Worker {
+    requests: Sender<Request>,
+    responses: Receiver<Response>,
+}
+
+impl Worker {
+    // ends once the worker is dropped and the query in progress is done
+    pub fn spawn(source: DataSource, client: Client) -> Worker {
+        let (requests, incoming) = mpsc::channel::<Request>();
+        let (outgoing, responses) = mpsc::channel();
+
+        thread::spawn(move || {
+            for request in incoming {
+                let mut retries = Vec::new();
+                let result = match &source {
+                    DataSource::Api(endpoints) => Info::query_with_retry(&client, endpoints, request.trip.as_ref(), RETRY_ATTEMPTS, RETRY_BASE_DELAY,
+                        request.budget, |attempt, e| retries.push(format!("attempt {} failed, retrying: {}", attempt, e))),
+                    DataSource::Files(paths) => Info::from_file(paths),
+                };
+                let response = Response { result, retries, fetched_trip: request.trip.is_none() };
+                if outgoing.send(response).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Worker { requests, responses }
+    }
+
+    // false if the thread is gone and nothing will come back
+    pub fn request(&self, trip: Option<TripInfo>, budget: Duration) -> bool {
+        self.requests.send(Request { trip, budget }).is_ok()
+    }
+
+    // never blocks, None while the query is still on its way
+    pub fn try_recv(&self) -> Option<Response> {
+        self.responses.try_recv().ok()
+    }
+}
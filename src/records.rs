@@ -0,0 +1,43 @@
+// All-time records kept across sessions in ~/.local/state/bahn-status/records.json
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{api::Info, state};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TopSpeed {
+    pub speed: f64, // km/h
+    pub time: u64,  // server time, milliseconds since the epoch
+    pub train_type: String,
+    pub vzn: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Records {
+    pub top_speed: Option<TopSpeed>,
+}
+
+impl Records {
+    pub fn path() -> Option<PathBuf> {
+        state::path("records.json")
+    }
+
+    // true if the update beat the stored top speed
+    pub fn update(&mut self, info: &Info) -> bool {
+        let speed = info.status.speed;
+        if self.top_speed.as_ref().is_some_and(|top| top.speed >= speed) || speed <= 0.0 {
+            return false;
+        }
+
+        self.top_speed = Some(TopSpeed {
+            speed,
+            time: info.status.serverTime,
+            train_type: info.trip.trip.trainType.clone(),
+            vzn: info.trip.trip.vzn.clone(),
+        });
+        true
+    }
+}
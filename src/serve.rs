@@ -0,0 +1,58 @@
+// Minimal local HTTP server handing out the newest data as JSON at /status and for Prometheus at /metrics
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{api::Info, metrics};
+
+pub type Latest = Arc<Mutex<Option<Info>>>;
+
+const JSON: &str = "application/json";
+const PROMETHEUS: &str = "text/plain; version=0.0.4";
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2); // one client at a time, so none may hang on forever
+
+// binds right away so a taken port is reported before the UI starts
+pub fn spawn(port: u16, latest: &Latest) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+
+    let shared = Arc::clone(latest);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // a misbehaving client must not take the server down
+            let _ = respond(stream, &shared);
+        }
+    });
+
+    Ok(())
+}
+
+fn respond(mut stream: TcpStream, latest: &Latest) -> io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, content_type, body) = match path {
+        "/status" => {
+            let json = latest.lock().expect("Poisoned").as_ref().map(serde_json::to_string);
+            match json {
+                Some(Ok(json)) => ("200 OK", JSON, json),
+                Some(Err(e)) => ("500 Internal Server Error", JSON, serde_json::json!({ "error": e.to_string() }).to_string()),
+                None => ("503 Service Unavailable", JSON, String::from("{\"error\":\"no data yet\"}")),
+            }
+        }
+        // no data yet is an empty scrape rather than an error
+        "/metrics" => ("200 OK", PROMETHEUS, latest.lock().expect("Poisoned").as_ref().map(metrics::render).unwrap_or_default()),
+        _ => ("404 Not Found", JSON, String::from("{\"error\":\"not found\"}")),
+    };
+
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body)
+}
@@ -0,0 +1,35 @@
+// Files kept across sessions in $XDG_STATE_HOME/bahn-status, falling back to ~/.local/state
+
+use std::{
+    env,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub fn path(name: &str) -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))?;
+
+    Some(base.join("bahn-status").join(name))
+}
+
+// nothing saved yet is the default, a broken file is an error
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> Result<T, Box<dyn Error>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("{}: {}", path.display(), e).into()),
+    }
+}
+
+pub fn save<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(value)?)
+}
@@ -0,0 +1,124 @@
+// Numbers derived from the updates, kept apart from drawing them
+
+use std::collections::VecDeque;
+
+use crate::api::Info;
+
+// share of the total in percent, the portal sometimes reports no total distance (yet)
+pub fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64 * 100.0
+    }
+}
+
+// km/h over the newest `window` updates, the whole buffer smooths away what is happening now
+pub fn average_speed(data: &VecDeque<Info>, window: usize) -> f64 {
+    let window = window.clamp(1, data.len().max(1));
+    data.iter().rev().take(window).fold(0.0, |acc, e| acc + e.status.speed) / window as f64
+}
+
+// slower than this is standing at a station (or creeping into it)
+pub const MOVING_SPEED: f64 = 5.0;
+
+// like average_speed, but only over the updates in the window where the train was moving,
+// None if it stood still the whole time
+pub fn moving_average_speed(data: &VecDeque<Info>, window: usize) -> Option<f64> {
+    let window = window.clamp(1, data.len().max(1));
+    let (sum, count) = data.iter().rev().take(window)
+        .map(|e| e.status.speed)
+        .filter(|&speed| speed >= MOVING_SPEED)
+        .fold((0.0, 0), |(sum, count), speed| (sum + speed, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+// whole minutes between two portal times (milliseconds), negative if early
+pub fn delay_minutes(scheduled: u64, actual: u64) -> i64 {
+    (actual as i64 - scheduled as i64) / 1000 / 60
+}
+
+// meters left to the final stop from a position (meters from the start)
+pub fn remaining(info: &Info, position: u64) -> u64 {
+    info.trip.trip.totalDistance.saturating_sub(position)
+}
+
+// where the train should be `seconds` after the update at the reported speed,
+// without driving past the next stop or the end of the line
+pub fn extrapolated_position(info: &Info, seconds: f64) -> u64 {
+    let trip = &info.trip.trip;
+    let reported = trip.actualPosition;
+    let driven = info.status.speed / 3.6 * seconds;
+    let limit = trip.next_stop().map_or(trip.totalDistance, |next| next.info.distanceFromStart).max(reported);
+    (reported + driven as u64).min(limit)
+}
+
+// seconds to cover a distance in meters, None while (nearly) standing since that would take forever
+pub fn travel_seconds(meters: u64, kmh: f64) -> Option<u64> {
+    (kmh >= 1.0).then(|| (meters as f64 / (kmh / 3.6)) as u64)
+}
+
+// seconds to the next stop at the current speed
+pub fn next_stop_seconds(info: &Info, position: u64) -> Option<u64> {
+    let next = info.trip.trip.next_stop()?;
+    travel_seconds(next.distance_from(position), info.status.speed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedRange {
+    pub current: f64,
+    pub average: f64,
+    pub min: f64, // slowest while moving, standing at a station doesn't count
+    pub max: f64,
+}
+
+// in whatever unit the speeds are given, all zero without any
+pub fn speed_range(speeds: &[f64]) -> SpeedRange {
+    let (sum, min, max) = speeds.iter().fold((0.0, f64::INFINITY, 0.0), |(sum, min, max): (f64, f64, f64), &speed| {
+        (sum + speed, if speed > 0.0 { min.min(speed) } else { min }, max.max(speed))
+    });
+
+    SpeedRange {
+        current: speeds.last().copied().unwrap_or(0.0),
+        average: if speeds.is_empty() { 0.0 } else { sum / speeds.len() as f64 },
+        min: if min.is_finite() { min } else { 0.0 },
+        max,
+    }
+}
+
+// at the final stop, by distance or because the portal says it's passed
+pub fn arrived(info: &Info) -> bool {
+    let trip = &info.trip.trip;
+    (trip.totalDistance > 0 && trip.actualPosition >= trip.totalDistance)
+        || trip.stops.last().is_some_and(|stop| stop.info.passed)
+}
+
+pub fn passed_stops(info: &Info) -> usize {
+    info.trip.trip.stops.iter().filter(|stop| stop.info.passed).count()
+}
+
+// what happened between the oldest and the newest buffered update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Recap {
+    pub distance: u64, // meters
+    pub max_speed: f64,
+    pub average_speed: f64,
+    pub stops: usize, // passed in between
+    pub delay: Option<i64>, // change in minutes, None if the portal didn't know it at both ends
+}
+
+pub fn recap(data: &VecDeque<Info>) -> Option<Recap> {
+    let (first, last) = (data.front()?, data.back()?);
+    let delay = match (first.trip.trip.final_delay(), last.trip.trip.final_delay()) {
+        (Some(first), Some(last)) => Some(last - first),
+        _ => None,
+    };
+
+    Some(Recap {
+        distance: last.trip.trip.actualPosition.saturating_sub(first.trip.trip.actualPosition),
+        max_speed: data.iter().fold(0.0, |max: f64, e| max.max(e.status.speed)),
+        average_speed: average_speed(data, data.len()),
+        stops: passed_stops(last).saturating_sub(passed_stops(first)),
+        delay,
+    })
+}
@@ -0,0 +1,44 @@
+// Fitting text into terminal columns: "ü" may be one char or two (u and a combining mark),
+// either way it takes one column, and wide characters take two
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+// columns the text takes up
+pub fn width(s: &str) -> usize {
+    s.width()
+}
+
+// filled up with spaces to `width` columns, longer text is left as it is
+pub fn pad(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(s.width())))
+}
+
+// label/value rows with the values lined up
+pub fn aligned(rows: &[(&str, String)]) -> String {
+    let width = rows.iter().map(|(label, _)| label.width()).max().unwrap_or(0) + 1;
+    rows.iter()
+        .map(|(label, value)| format!("{}{}", pad(label, width), value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// cut to at most `width` columns, marking the cut with …
+pub fn ellipsize(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return s.to_owned();
+    }
+
+    let mut result = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        // combining marks stay with the character before
+        let w = c.width().unwrap_or(0);
+        if w > 0 && used + w + 1 > width {
+            break;
+        }
+        result.push(c);
+        used += w;
+    }
+    result.push('…');
+    result
+}
@@ -0,0 +1,135 @@
+// Colors of the UI, the [theme] table in the config: a preset and whatever should differ from it
+
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use serde::{de, Deserialize, Deserializer};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Default,
+    Colorblind, // no red against green, delays also told apart by weight
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Preset, String> {
+        match s {
+            "default" => Ok(Preset::Default),
+            "colorblind" => Ok(Preset::Colorblind),
+            _ => Err(format!("Unknown theme: {} (expected default or colorblind)", s)),
+        }
+    }
+}
+
+// how the speed graph goes from slow to fast with speed_colors = "speed"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedScale {
+    #[default]
+    Rainbow, // blue, green, red
+    BlueOrange,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(from = "ThemeTable")]
+pub struct Theme {
+    pub focused_border: Color,
+    pub graph_up: Color, // speeding up with speed_colors = "direction"
+    pub graph_down: Color,
+    pub speed_scale: SpeedScale,
+    pub delay_ok: Color, // below delay_colors.yellow, also the delay graph going down
+    pub delay_warn: Color,
+    pub delay_bad: Color, // from delay_colors.red on, also the delay graph going up
+    pub emphasize_delays: bool, // bold from yellow on, underlined from red on
+    pub passed_station: Color, // the route line as far as we got
+    pub next_station: Color,
+}
+
+impl Theme {
+    pub fn preset(preset: Preset) -> Theme {
+        match preset {
+            Preset::Default => Theme {
+                focused_border: Color::Magenta,
+                graph_up: Color::Green,
+                graph_down: Color::Red,
+                speed_scale: SpeedScale::Rainbow,
+                delay_ok: Color::Green,
+                delay_warn: Color::Yellow,
+                delay_bad: Color::Red,
+                emphasize_delays: false,
+                passed_station: Color::Green,
+                next_station: Color::Yellow,
+            },
+            // Okabe-Ito colors, which stay apart with all common kinds of color blindness
+            Preset::Colorblind => Theme {
+                focused_border: Color::Rgb(0xcc, 0x79, 0xa7),
+                graph_up: Color::Rgb(0x56, 0xb4, 0xe9),
+                graph_down: Color::Rgb(0xe6, 0x9f, 0x00),
+                speed_scale: SpeedScale::BlueOrange,
+                delay_ok: Color::Rgb(0x56, 0xb4, 0xe9),
+                delay_warn: Color::Rgb(0xf0, 0xe4, 0x42),
+                delay_bad: Color::Rgb(0xd5, 0x5e, 0x00),
+                emphasize_delays: true,
+                passed_station: Color::Rgb(0x00, 0x72, 0xb2),
+                next_station: Color::Rgb(0xf0, 0xe4, 0x42),
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::preset(Preset::default())
+    }
+}
+
+// names like "red" or "lightblue", an index like "208" or "#rrggbb"
+#[derive(Debug, Clone, Copy)]
+struct ThemeColor(Color);
+
+impl<'de> Deserialize<'de> for ThemeColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ThemeColor, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map(ThemeColor).map_err(|_| de::Error::custom(format!("unknown color \"{}\"", name)))
+    }
+}
+
+// as written in the config, everything not given comes from the preset
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ThemeTable {
+    preset: Preset,
+    focused_border: Option<ThemeColor>,
+    graph_up: Option<ThemeColor>,
+    graph_down: Option<ThemeColor>,
+    speed_scale: Option<SpeedScale>,
+    delay_ok: Option<ThemeColor>,
+    delay_warn: Option<ThemeColor>,
+    delay_bad: Option<ThemeColor>,
+    emphasize_delays: Option<bool>,
+    passed_station: Option<ThemeColor>,
+    next_station: Option<ThemeColor>,
+}
+
+impl From<ThemeTable> for Theme {
+    fn from(table: ThemeTable) -> Theme {
+        let base = Theme::preset(table.preset);
+        let color = |configured: Option<ThemeColor>, preset: Color| configured.map_or(preset, |color| color.0);
+        Theme {
+            focused_border: color(table.focused_border, base.focused_border),
+            graph_up: color(table.graph_up, base.graph_up),
+            graph_down: color(table.graph_down, base.graph_down),
+            speed_scale: table.speed_scale.unwrap_or(base.speed_scale),
+            delay_ok: color(table.delay_ok, base.delay_ok),
+            delay_warn: color(table.delay_warn, base.delay_warn),
+            delay_bad: color(table.delay_bad, base.delay_bad),
+            emphasize_delays: table.emphasize_delays.unwrap_or(base.emphasize_delays),
+            passed_station: color(table.passed_station, base.passed_station),
+            next_station: color(table.next_station, base.next_station),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Speeds and distances in metric or imperial units, times of day in 24h or 12h and some timezone
+
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+const KM_PER_MILE: f64 = 1.609344;
+const METERS_PER_FOOT: f64 = 0.3048;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    pub fn toggle(self) -> Units {
+        match self {
+            Units::Metric => Units::Imperial,
+            Units::Imperial => Units::Metric,
+        }
+    }
+
+    // km/h (as the portal sends it) in the display unit
+    pub fn speed(self, kmh: f64) -> f64 {
+        match self {
+            Units::Metric => kmh,
+            Units::Imperial => kmh / KM_PER_MILE,
+        }
+    }
+
+    // meters in the display unit's (kilo)meters or miles
+    pub fn distance(self, meters: u64) -> f64 {
+        match self {
+            Units::Metric => meters as f64 / 1000.0,
+            Units::Imperial => meters as f64 / 1000.0 / KM_PER_MILE,
+        }
+    }
+
+    pub fn speed_unit(self) -> &'static str {
+        match self {
+            Units::Metric => "km/h",
+            Units::Imperial => "mph",
+        }
+    }
+
+    pub fn distance_unit(self) -> &'static str {
+        match self {
+            Units::Metric => "km",
+            Units::Imperial => "mi",
+        }
+    }
+}
+
+// separators differ per language: 1.234,5 vs 1,234.5
+#[derive(Debug, Clone, Copy)]
+pub struct NumberFormat {
+    pub thousands: char,
+    pub decimal: char,
+}
+
+pub fn fmt_number(value: f64, decimals: usize, format: NumberFormat) -> String {
+    let plain = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = match plain.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (plain.as_str(), None),
+    };
+
+    let mut result = String::new();
+    if value < 0.0 {
+        result.push('-');
+    }
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            result.push(format.thousands);
+        }
+        result.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        result.push(format.decimal);
+        result.push_str(fraction);
+    }
+
+    result
+}
+
+pub fn fmt_speed(kmh: f64, units: Units, format: NumberFormat) -> String {
+    format!("{}{}", fmt_number(units.speed(kmh), 0, format), units.speed_unit())
+}
+
+// degrees with the hemisphere instead of a sign, e.g. (50.570N, 8.660E)
+pub fn fmt_coordinates(latitude: f64, longitude: f64) -> String {
+    format!("({:.03}{}, {:.03}{})", latitude.abs(), if latitude < 0.0 { 'S' } else { 'N' },
+        longitude.abs(), if longitude < 0.0 { 'W' } else { 'E' })
+}
+
+// the portal counts distances in meters; below one km (or mile) "0km" would look like we're there
+pub fn fmt_distance(meters: u64, units: Units, format: NumberFormat) -> String {
+    let km = meters as f64 / 1000.0;
+    match units {
+        Units::Metric if meters < 1000 => format!("{}m", meters),
+        Units::Metric => format!("{}{}", fmt_number(km, 0, format), units.distance_unit()),
+        Units::Imperial if km < KM_PER_MILE => {
+            format!("{}ft", fmt_number(meters as f64 / METERS_PER_FOOT, 0, format))
+        }
+        Units::Imperial => format!("{}{}", fmt_number(km / KM_PER_MILE, 0, format), units.distance_unit()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub enum Clock {
+    #[default]
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "12h")]
+    H12,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeFormat {
+    pub clock: Clock,
+    pub zone: Option<Tz>, // the system's if not set
+}
+
+// the portal's timestamps are milliseconds since the epoch
+pub fn utc_time(millis: u64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis as i64).unwrap_or_default()
+}
+
+pub fn fmt_time(time: DateTime<Utc>, format: TimeFormat, seconds: bool) -> String {
+    let pattern = match (format.clock, seconds) {
+        (Clock::H24, false) => "%H:%M",
+        (Clock::H24, true) => "%H:%M:%S",
+        (Clock::H12, false) => "%I:%M %p",
+        (Clock::H12, true) => "%I:%M:%S %p",
+    };
+
+    match format.zone {
+        Some(zone) => time.with_timezone(&zone).format(pattern).to_string(),
+        None => time.with_timezone(&Local).format(pattern).to_string(),
+    }
+}
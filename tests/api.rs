@@ -0,0 +1,42 @@
+// Portal detection against a local server answering every request the same
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+};
+
+use bahn_status::api::{self, ApiEndpoints};
+
+// base url of a server that answers with `body` until the test ends
+fn portal(body: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("free port");
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = String::new();
+            let _ = BufReader::new(&stream).read_line(&mut request);
+            let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(), body);
+        }
+    });
+    base
+}
+
+#[test]
+fn detect_takes_a_portal_with_a_status() {
+    let status = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/sample/status.json")).unwrap();
+    let portal = [portal(status)];
+    let detected = ApiEndpoints::detect(&api::client().unwrap(), &portal, |_| ());
+    assert_eq!(detected.map(|endpoints| endpoints.status), Some(ApiEndpoints::at(&portal[0]).status));
+}
+
+#[test]
+fn detect_passes_over_error_objects() {
+    let portal = [portal(String::from(r#"{"error":"login required"}"#))];
+    let mut probed = Vec::new();
+    let detected = ApiEndpoints::detect(&api::client().unwrap(), &portal, |base| probed.push(base.to_owned()));
+    assert!(detected.is_none());
+    assert_eq!(probed, portal);
+}
@@ -0,0 +1,20 @@
+// The recorded portal responses in sample/, shared by the tests
+
+// compiled into every test on its own, not each one uses all of it
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use bahn_status::api::{ApiPaths, Info};
+
+pub fn paths() -> ApiPaths {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("sample");
+    ApiPaths {
+        status: dir.join("status.json"),
+        trip: dir.join("trip.json"),
+    }
+}
+
+pub fn sample() -> Info {
+    Info::from_file(&paths()).expect("sample should parse")
+}
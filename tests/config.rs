@@ -0,0 +1,22 @@
+// Settings that are taken with a grain of salt
+
+use std::time::Duration;
+
+use bahn_status::config::Config;
+
+fn config(toml: &str) -> Config {
+    toml::from_str(toml).expect("config should parse")
+}
+
+#[test]
+fn tick_rate_has_a_floor() {
+    assert_eq!(config("tick_rate_ms = 0").tick_rate(), Duration::from_millis(100));
+    assert_eq!(config("poll_ms = 250").tick_rate(), Duration::from_millis(250));
+}
+
+#[test]
+fn redraws_at_least_once_per_tick() {
+    assert_eq!(config("tick_rate_ms = 0\nredraw_ms = 0").redraw_interval(), Duration::from_millis(20));
+    assert_eq!(config("tick_rate_ms = 0\nredraw_ms = 500").redraw_interval(), Duration::from_millis(100));
+    assert_eq!(Config::default().redraw_interval(), Duration::from_millis(200));
+}
@@ -0,0 +1,83 @@
+// The recorded portal responses in sample/ going through the same parsing as the live ones
+
+mod common;
+
+use bahn_status::api::{Signal, StatusInfo, StopPosition, TripInfo};
+use common::sample;
+
+#[test]
+fn status_deserializes() {
+    let status = sample().status;
+    assert_eq!(status.trainType, "ICE");
+    assert_eq!(status.gpsStatus, "VALID");
+    assert_eq!(status.series_name(), Some("ICE 4"));
+    assert_eq!(Signal::parse(&status.internet), Signal::High);
+}
+
+#[test]
+fn trip_deserializes() {
+    let trip = sample().trip.trip;
+    assert_eq!(trip.vzn, "1672");
+    assert_eq!(trip.stops.len(), 22);
+    assert_eq!(trip.totalDistance, 735150);
+    assert!(trip.stops.iter().take_while(|stop| stop.info.passed).count() < trip.stops.len());
+}
+
+#[test]
+fn next_stop_follows_the_portal() {
+    let trip = sample().trip.trip;
+    let next = trip.next_stop().expect("sample has a next stop");
+    assert_eq!(next.station.evaNr, trip.stopInfo.actualNext);
+    assert_eq!(next.station.name, "Marburg(Lahn)");
+    assert!(next.distance_from(trip.actualPosition) > 0);
+}
+
+#[test]
+fn next_stop_falls_back_to_the_plan() {
+    let mut trip = sample().trip.trip;
+    trip.stopInfo.actualNext = String::from("nowhere");
+    assert_eq!(trip.next_stop().map(|stop| &stop.station.evaNr), Some(&trip.stopInfo.scheduledNext));
+
+    trip.stopInfo.scheduledNext = String::from("nowhere");
+    assert!(trip.next_stop().is_none());
+}
+
+#[test]
+fn final_delay_needs_an_actual_time() {
+    let mut trip = sample().trip.trip;
+    assert_eq!(trip.final_delay(), Some(0));
+
+    let last = trip.stops.last_mut().unwrap();
+    last.timetable.actualArrivalTime = last.timetable.scheduledArrivalTime.map(|time| time + 7 * 60 * 1000);
+    assert_eq!(trip.final_delay(), Some(7));
+
+    trip.stops.last_mut().unwrap().timetable.actualArrivalTime = None;
+    assert_eq!(trip.final_delay(), None);
+}
+
+#[test]
+fn position_status_goes_by_passed_when_unknown() {
+    let mut trip = sample().trip.trip;
+    let positions: Vec<StopPosition> = trip.stops.iter().map(|stop| stop.info.position()).collect();
+    assert_eq!(positions[..3], [StopPosition::Passed, StopPosition::Passed, StopPosition::Departed]);
+    assert_eq!(positions[3], StopPosition::Future);
+
+    let stop = &mut trip.stops[0].info;
+    stop.positionStatus = String::from("somewhere");
+    assert_eq!(stop.position(), StopPosition::Passed);
+    stop.passed = false;
+    assert_eq!(stop.position(), StopPosition::Future);
+}
+
+#[test]
+fn error_objects_are_no_answer() {
+    assert!(serde_json::from_str::<StatusInfo>(r#"{"error":"login required"}"#).is_err());
+    assert!(serde_json::from_str::<TripInfo>("{}").is_err());
+}
+
+#[test]
+fn missing_details_fall_back_to_defaults() {
+    let status: StatusInfo = serde_json::from_str(r#"{"serverTime":1721369676107}"#).expect("only serverTime is required");
+    assert_eq!(status.speed, 0.0);
+    assert_eq!(Signal::parse(&status.internet), Signal::Unknown);
+}
@@ -0,0 +1,44 @@
+// Saving the buffered updates and picking them up again after a restart
+
+mod common;
+
+use std::{collections::VecDeque, env, fs};
+
+use bahn_status::{api::Info, history, state};
+use common::sample;
+
+#[test]
+fn saved_history_loads_again() {
+    let path = env::temp_dir().join(format!("bahn-status-test-{}", std::process::id())).join("history.json");
+    let mut estimated = sample();
+    estimated.speed_estimated = true;
+    let saved: VecDeque<Info> = [sample(), estimated].into_iter().collect();
+    state::save(&path, &saved).expect("temp dir should be writable");
+
+    let loaded: VecDeque<Info> = state::load(&path).expect("just saved");
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded[1].status.serverTime, saved[1].status.serverTime);
+    assert_eq!(loaded[0].trip.trip.vzn, "1672");
+    // a GPS estimate stays one after a restart
+    assert!(!loaded[0].speed_estimated && loaded[1].speed_estimated);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    assert!(state::load::<VecDeque<Info>>(&path).expect("missing is no error").is_empty());
+}
+
+#[test]
+fn only_the_same_recent_trip_continues() {
+    let saved: VecDeque<Info> = [sample()].into_iter().collect();
+    assert!(history::continues(&saved, &sample()));
+    assert!(!history::continues(&VecDeque::new(), &sample()));
+
+    let mut other = sample();
+    other.trip.trip.vzn = String::from("1000");
+    assert!(!history::continues(&saved, &other));
+
+    // replayed files count as fresh, so age it by hand
+    let mut stale = sample();
+    stale.status.serverTime -= 15 * 60 * 1000;
+    let old: VecDeque<Info> = [stale].into_iter().collect();
+    assert!(!history::continues(&old, &sample()));
+}
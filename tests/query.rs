@@ -0,0 +1,49 @@
+// The query thread answering from the recorded samples
+
+mod common;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use bahn_status::{
+    api::DataSource,
+    query::{Response, Worker},
+};
+use reqwest::blocking::Client;
+
+fn worker() -> Worker {
+    Worker::spawn(DataSource::Files(common::paths()), Client::new())
+}
+
+fn wait(worker: &Worker) -> Response {
+    let start = Instant::now();
+    loop {
+        if let Some(response) = worker.try_recv() {
+            return response;
+        }
+        assert!(start.elapsed() < Duration::from_secs(5), "no response");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn nothing_comes_without_asking() {
+    let worker = worker();
+    thread::sleep(Duration::from_millis(50));
+    assert!(worker.try_recv().is_none());
+}
+
+#[test]
+fn answers_each_request() {
+    let worker = worker();
+    assert!(worker.request(None, Duration::from_secs(1)));
+    let response = wait(&worker);
+    let info = response.result.expect("sample should parse");
+    assert!(response.fetched_trip);
+    assert!(response.retries.is_empty());
+
+    assert!(worker.request(Some(info.trip), Duration::from_secs(1)));
+    assert!(!wait(&worker).fetched_trip);
+}
@@ -0,0 +1,165 @@
+// Calculations behind the panels, without a terminal
+
+use std::collections::VecDeque;
+
+use bahn_status::{
+    api::{Info, StatusInfo, Stop},
+    stats::{self, SpeedRange},
+    units::{self, NumberFormat, Units},
+};
+
+const GERMAN: NumberFormat = NumberFormat { thousands: '.', decimal: ',' };
+
+fn at_speeds(speeds: &[f64]) -> VecDeque<Info> {
+    speeds
+        .iter()
+        .map(|&speed| {
+            let mut info = Info::default();
+            info.status.speed = speed;
+            info
+        })
+        .collect()
+}
+
+#[test]
+fn percent_of_nothing_is_zero() {
+    assert_eq!(stats::percent(50, 200), 25.0);
+    assert_eq!(stats::percent(200, 200), 100.0);
+    assert_eq!(stats::percent(50, 0), 0.0);
+}
+
+#[test]
+fn average_speed_takes_the_newest() {
+    let data = at_speeds(&[300.0, 100.0, 200.0]);
+    assert_eq!(stats::average_speed(&data, 2), 150.0);
+    assert_eq!(stats::average_speed(&data, 3), 200.0);
+    // larger windows than the buffer and empty buffers are fine
+    assert_eq!(stats::average_speed(&data, 10), 200.0);
+    assert_eq!(stats::average_speed(&data, 0), 200.0);
+    assert_eq!(stats::average_speed(&VecDeque::new(), 10), 0.0);
+}
+
+#[test]
+fn delay_in_whole_minutes() {
+    let scheduled = 1_721_402_400_000;
+    assert_eq!(stats::delay_minutes(scheduled, scheduled), 0);
+    assert_eq!(stats::delay_minutes(scheduled, scheduled + 5 * 60 * 1000 + 59 * 1000), 5);
+    assert_eq!(stats::delay_minutes(scheduled, scheduled - 2 * 60 * 1000), -2);
+}
+
+#[test]
+fn numbers_per_language() {
+    assert_eq!(units::fmt_number(1234.5, 1, GERMAN), "1.234,5");
+    assert_eq!(units::fmt_number(-7.0, 0, GERMAN), "-7");
+    assert_eq!(units::fmt_speed(160.9344, Units::Imperial, GERMAN), "100mph");
+    assert_eq!(units::fmt_distance(950, Units::Metric, GERMAN), "950m");
+    assert_eq!(units::fmt_distance(12_300, Units::Metric, GERMAN), "12km");
+}
+
+#[test]
+fn coordinates_by_hemisphere() {
+    assert_eq!(units::fmt_coordinates(50.5701, 8.6604), "(50.570N, 8.660E)");
+    assert_eq!(units::fmt_coordinates(-33.9, -70.65), "(33.900S, 70.650W)");
+}
+
+#[test]
+fn gps_speed_from_two_positions() {
+    let before = StatusInfo { latitude: 50.0, longitude: 8.0, serverTime: 0, ..StatusInfo::default() };
+    // 0.01° north is about 1.1 km, in 20 s that is about 200 km/h
+    let after = StatusInfo { latitude: 50.01, serverTime: 20_000, ..before.clone() };
+    let kmh = after.gps_speed(&before).expect("train moved");
+    assert!((kmh - 200.0).abs() < 1.0, "{}", kmh);
+
+    // standing, time going backwards and impossible jumps give nothing
+    assert_eq!(before.gps_speed(&before), None);
+    assert_eq!(before.gps_speed(&after), None);
+    let jump = StatusInfo { latitude: 51.0, ..after.clone() };
+    assert_eq!(jump.gps_speed(&before), None);
+}
+
+// stops every 10 km, the first `passed` of them behind us
+fn on_route(position: u64, passed: usize) -> Info {
+    let mut info = Info::default();
+    let trip = &mut info.trip.trip;
+    for i in 0..5 {
+        let mut stop = Stop::default();
+        stop.station.evaNr = i.to_string();
+        stop.info.distanceFromStart = i as u64 * 10_000;
+        stop.info.passed = i < passed;
+        trip.stops.push(stop);
+    }
+    trip.totalDistance = 40_000;
+    trip.actualPosition = position;
+    trip.stopInfo.actualNext = passed.to_string();
+    info
+}
+
+#[test]
+fn remaining_distance() {
+    let info = on_route(15_000, 2);
+    assert_eq!(stats::remaining(&info, 15_000), 25_000);
+    assert_eq!(stats::remaining(&info, 50_000), 0);
+}
+
+#[test]
+fn position_stops_at_the_next_stop() {
+    let mut info = on_route(15_000, 2);
+    info.status.speed = 36.0; // 10 m/s
+    assert_eq!(stats::extrapolated_position(&info, 0.0), 15_000);
+    assert_eq!(stats::extrapolated_position(&info, 100.0), 16_000);
+    assert_eq!(stats::extrapolated_position(&info, 10_000.0), 20_000);
+}
+
+#[test]
+fn countdown_to_the_next_stop() {
+    let mut info = on_route(15_000, 2);
+    info.status.speed = 180.0; // 50 m/s
+    assert_eq!(stats::next_stop_seconds(&info, 15_000), Some(100));
+
+    info.status.speed = 0.0;
+    assert_eq!(stats::next_stop_seconds(&info, 15_000), None);
+    assert_eq!(stats::travel_seconds(1000, 0.5), None);
+}
+
+#[test]
+fn speed_range_ignores_standing() {
+    let range = stats::speed_range(&[0.0, 120.0, 200.0, 80.0]);
+    assert_eq!(range, SpeedRange { current: 80.0, average: 100.0, min: 80.0, max: 200.0 });
+    assert_eq!(stats::speed_range(&[]), SpeedRange { current: 0.0, average: 0.0, min: 0.0, max: 0.0 });
+}
+
+#[test]
+fn recap_of_the_buffer() {
+    assert_eq!(stats::recap(&VecDeque::new()), None);
+
+    let mut data: VecDeque<Info> = [on_route(5_000, 1), on_route(25_000, 3)].into_iter().collect();
+    data[0].status.speed = 100.0;
+    data[1].status.speed = 200.0;
+    let recap = stats::recap(&data).unwrap();
+    assert_eq!(recap.distance, 20_000);
+    assert_eq!(recap.max_speed, 200.0);
+    assert_eq!(recap.average_speed, 150.0);
+    assert_eq!(recap.stops, 2);
+    assert_eq!(recap.delay, None);
+}
+
+#[test]
+fn arrival_by_distance_or_last_stop() {
+    assert!(!stats::arrived(&on_route(15_000, 2)));
+    assert!(stats::arrived(&on_route(40_000, 4)));
+    assert!(stats::arrived(&on_route(39_000, 5)));
+    // no total distance known doesn't mean we're there
+    let mut info = on_route(0, 0);
+    info.trip.trip.totalDistance = 0;
+    assert!(!stats::arrived(&info));
+}
+
+#[test]
+fn moving_average_leaves_out_standing() {
+    let data = at_speeds(&[200.0, 0.0, 0.0, 100.0, 2.0]);
+    assert_eq!(stats::average_speed(&data, 5), 60.4);
+    assert_eq!(stats::moving_average_speed(&data, 5), Some(150.0));
+    assert_eq!(stats::moving_average_speed(&data, 3), Some(100.0));
+    assert_eq!(stats::moving_average_speed(&data, 2), Some(100.0));
+    assert_eq!(stats::moving_average_speed(&at_speeds(&[0.0, 1.0]), 2), None);
+}
@@ -0,0 +1,42 @@
+// Column counting with umlauts, combining marks and wide characters
+
+use bahn_status::text::{self, aligned, ellipsize};
+
+// "ü" as u followed by a combining diaeresis, as some sources send it
+const DECOMPOSED: &str = "Mu\u{308}llheim (Baden)";
+
+#[test]
+fn width_counts_columns() {
+    assert_eq!(text::width("Müllheim (Baden)"), 16);
+    assert_eq!(text::width(DECOMPOSED), 16);
+    assert_eq!(text::width("東京"), 4);
+}
+
+#[test]
+fn values_line_up_after_umlauts() {
+    let rows = aligned(&[
+        (DECOMPOSED, String::from("1")),
+        ("Mannheim Hbf", String::from("2")),
+        ("Göttingen", String::from("3")),
+    ]);
+    let columns: Vec<usize> = rows.lines().map(|row| text::width(row.trim_end_matches(char::is_numeric))).collect();
+    assert_eq!(columns, vec![17, 17, 17]);
+}
+
+#[test]
+fn pad_to_columns() {
+    assert_eq!(text::pad(DECOMPOSED, 18), format!("{}  ", DECOMPOSED));
+    assert_eq!(text::pad("東京", 5), "東京 ");
+    assert_eq!(text::pad("Köln", 2), "Köln");
+}
+
+#[test]
+fn ellipsize_by_columns() {
+    assert_eq!(ellipsize("Köln Hbf", 8), "Köln Hbf");
+    assert_eq!(ellipsize("Müllheim (Baden)", 9), "Müllheim…");
+    // the mark stays with its u
+    assert_eq!(ellipsize(DECOMPOSED, 3), "Mu\u{308}…");
+    assert_eq!(text::width(&ellipsize(DECOMPOSED, 9)), 9);
+    // a wide character that doesn't fit anymore is left out whole
+    assert_eq!(ellipsize("東京駅", 4), "東…");
+}